@@ -0,0 +1,76 @@
+//! The dispatcher links of kind dispatch are copies of. Started through a link it reads the config of the command
+//! directory its in, picks the group like pman current does (PMAN_<NAME> env var, .pman-versions pin, active group)
+//! and runs that groups program with the original arguments.
+
+extern crate pman;
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process;
+use std::process::Command;
+
+use pman::{Config, Manager, PmanError};
+use pman::config::CmdDir;
+
+fn main() {
+	if let Err(e) = run() {
+		eprintln!("pman-shim: {}", e);
+		process::exit(e.exit_code());
+	}
+}
+
+fn run() -> Result<(), PmanError> {
+	let exe_path = env::current_exe()?;
+	let link_path = env::args_os().next().map(PathBuf::from).unwrap_or_else(|| exe_path.clone());
+	// hard links and copies have their own path as current_exe, the name they were started with is the command
+	let file_name = link_path.file_name().or_else(|| exe_path.file_name()).unwrap().to_string_lossy().into_owned();
+	let command = file_name.strip_suffix(env::consts::EXE_SUFFIX).unwrap_or(&file_name).to_owned();
+	if command.starts_with("pman-shim") {
+		return Err(PmanError::InvalidArgs(String::from("pman-shim is run through the links pman makes in the command directory, \
+			make links of kind dispatch to use it")));
+	}
+
+	let cmd_dir = exe_path.parent().unwrap().to_path_buf();
+	let cfg_path = match CmdDir::config_pointer(&cmd_dir) {
+		Some(path) => path,
+		None => Config::cfg_path()?
+	};
+	// only reading, the config is replaced atomically so there is no need to wait for the lock. A legacy config is read as it is,
+	// migrating it is left to pman
+	let mut config = Config::read_from(&cfg_path)?.ok_or(PmanError::NotConfigured)?;
+	config.set_cmd_dir_override(Some(cmd_dir));
+	let manager = Manager::new(config, cfg_path);
+
+	let target = manager.dispatch_target(&command, &env::current_dir()?)?;
+	if _same_file(&target, &exe_path) {
+		return Err(PmanError::LinkConflict { name: command, reason: String::from("it dispatches to itself") });
+	}
+	let mut cmd = Command::new(&target);
+	cmd.args(env::args_os().skip(1));
+	return _exec(cmd, &target);
+}
+
+fn _same_file(a: &PathBuf, b: &PathBuf) -> bool {
+	return match (fs::canonicalize(a), fs::canonicalize(b)) {
+		(Ok(a), Ok(b)) => a == b,
+		_ => false
+	};
+}
+
+/// replaces the dispatcher with the program, so signals and the exit code go straight to whoever started it
+#[cfg(unix)]
+fn _exec(mut cmd: Command, target: &PathBuf) -> Result<(), PmanError> {
+	use std::os::unix::process::CommandExt;
+
+	let e = cmd.exec();
+	return Err(PmanError::from(io::Error::new(e.kind(), format!("Unable to run \"{}\", {}", target.display(), e))));
+}
+
+/// windows cant replace a running process, wait for the program and exit with its exit code
+#[cfg(not(unix))]
+fn _exec(mut cmd: Command, target: &PathBuf) -> Result<(), PmanError> {
+	let status = cmd.status().map_err(|e| io::Error::new(e.kind(), format!("Unable to run \"{}\", {}", target.display(), e)))?;
+	process::exit(status.code().unwrap_or(1));
+}
@@ -0,0 +1,76 @@
+use error::PmanError;
+use pman::Manager;
+use utils;
+
+pub const DESCRIPTION: &'static str = "Names groups, eg. lts, so they can be selected by name instead of priority";
+
+pub fn print_usage() {
+	println!("Usage: pman alias <name> [alias] [priority] <args>\n\
+			 \0   name       (required) Name of the configuration group\n\
+			 \0   alias      (optional) The alias to set, if ommited the aliases of the groups of name are listed.\n\
+			 \0              Aliases start with a letter followed by letters, digits, - and _\n\
+			 \0   priority   (required unless removing) The group closest to but no less than this priority gets the\n\
+			 \0              alias, it can be anything swap takes, eg. -1, =170, ^1.8 or another alias.\n\
+			 \0An alias is on one group of a name at most, setting it moves it off the group which had it.\n\
+			 \0Aliases can be used anywhere a priority is taken, eg. pman swap java lts. So can the built in\n\
+			 \0highest, lowest, active and previous (the group which was active before the last swap).\n\
+			 \0possible args are:\n\
+			 \0   -exact,-e  Gives the alias to exactly the given priority instead of the closest\n\
+			 \0   -remove,-r Removes the alias");
+}
+
+pub fn run(mut manager: Manager, args: &[String]) -> Result<(), PmanError> {
+	if args.len() == 0 {
+		print_usage();
+		return Err(PmanError::InvalidArgs(String::from("Expected arguments <name> [alias] [priority]")));
+	}
+	let name = &args[0];
+	if args.len() == 1 {
+		let aliases = manager.config().aliases(name);
+		if aliases.is_empty() {
+			println!("The groups of {} have no aliases", name);
+		}
+		for (alias, priority) in aliases {
+			println!("{} - {} {}", alias, name, priority);
+		}
+		return Ok(());
+	}
+	let alias = &args[1];
+	if args.len() < 3 {
+		print_usage();
+		return Err(PmanError::InvalidArgs(format!("Expected the priority of the group to give {} or -remove", alias)));
+	}
+
+	if args[2].eq_ignore_ascii_case("-r") || args[2].eq_ignore_ascii_case("-remove") {
+		match manager.config_mut().remove_alias(name, alias) {
+			Some(priority) => {
+				manager.save()?;
+				println!("Removed alias {} from {} {}", alias, name, priority);
+			},
+			None => return Err(PmanError::InvalidArgs(format!("No group of {} has the alias {}", name, alias)))
+		}
+		return Ok(());
+	}
+
+	let mut exact = false;
+	for arg in args[3..].iter() {
+		match arg.to_ascii_lowercase().as_ref() {
+			"-e" | "-exact" => exact = true,
+			_ => return Err(PmanError::InvalidArgs(format!("Unexpected or unknown argument '{}'", arg)))
+		}
+	}
+	let selector = utils::parse_selector(&args[2], exact)?;
+	let group = manager.config().select(name, &selector).ok_or_else(|| {
+		PmanError::MissingGroup {
+			name: name.to_owned(),
+			selector: selector.to_string()
+		}
+	})?;
+	let taken_from = manager.config_mut().set_alias(name, alias, group.priority)?;
+	manager.save()?;
+	println!("Alias {} selects {} {} - {}", alias, name, group.priority, group.base_path);
+	if let Some(priority) = taken_from {
+		println!("It was moved off {} {}", name, priority);
+	}
+	return Ok(());
+}
@@ -1,7 +1,10 @@
-use config::Config;
+use std::path::PathBuf;
+
+use config;
+use config::{CfgLocation, CfgSource, Config};
+use error::PmanError;
+use output::{self, Format, Report};
 use utils;
-use std;
-use std::ascii::AsciiExt;
 
 pub const DESCRIPTION: &'static str = "Utility to help configure pman. Expecially useful when running for the first time";
 
@@ -10,17 +13,27 @@ pub fn print_usage() {
 			 \0args are:\n\
 			 \0   -d,-dir <val>  Sets the directory to use for all command links. May want to run 'pman rebuild' afterwards.\n\
 			 \0                  Also may be used when running pman for the first time to configure the directory.\n\
-			 \0   -p,-prompt     Go through a series of prompts to update configuration.");
+			 \0   -p,-prompt     Go through a series of prompts to update configuration.\n\
+			 \0   -w,--where     Prints which config file is used and why, followed by every location searched for one.\n\
+			 \0                  Config files are searched for in this order:\n\
+			 \0                    the --cfg flag, the {cfg_env} env var, $XDG_CONFIG_HOME/pman/config.toml,\n\
+			 \0                    ~/.config/pman/config.toml and .pman next to pman (portable mode)\n\
+			 \0   --restore-backup [n]\n\
+			 \0                  Replaces the config with one of the last {backups} configs, 1 (the default) is the most recent.\n\
+			 \0                  The replaced config becomes backup 1, so a restore can be undone by restoring again.\n\
+			 \0                  May want to run 'pman rebuild' afterwards.", cfg_env=config::CONFIG_ENV, backups=config::BACKUP_COUNT);
 }
 
 
-pub fn run(config: Option<Config>, args: &[String]) {
+pub fn run(config: Option<Config>, location: &CfgLocation, format: Format, args: &[String]) -> Result<(), PmanError> {
 	if args.len() == 0 {
-		println!("At least 1 option is required");
 		print_usage();
+		return Err(PmanError::InvalidArgs(String::from("At least 1 option is required")));
 	} else {
 		let mut dir = String::new();
 		let mut do_prompt = false;
+		let mut do_where = false;
+		let mut restore_opt: Option<usize> = None;
 		let mut updated_message = String::new();
 		
 		let mut skip_n = 0; // used to skip arguments after one was already read
@@ -29,15 +42,25 @@ pub fn run(config: Option<Config>, args: &[String]) {
 				let val = &args[i];
 				match val.to_ascii_lowercase().as_ref() {
 					"-p" | "-prompt" => do_prompt = true,
+					"-w" | "-where" | "--where" => do_where = true,
+					"-restore-backup" | "--restore-backup" => {
+						// the backup number is optional
+						match args.get(i+1).and_then(|n| n.parse::<usize>().ok()) {
+							Some(n) => {
+								restore_opt = Some(n);
+								skip_n = 1;
+							},
+							None => restore_opt = Some(1)
+						}
+					},
 					"-d" | "-dir" => {
 						if i+1 >= args.len() {
-							println!("Invalid syntax, option -dir expects value after it");
-							std::process::exit(1);
+							return Err(PmanError::InvalidArgs(String::from("Invalid syntax, option -dir expects value after it")));
 						} else {
 							dir = args[i+1].clone();
 							skip_n = 1; // skip next argument
 							if updated_message.len() > 0 {
-								updated_message.push_str(&"\n");
+								updated_message.push('\n');
 							}
 							updated_message.push_str(&format!("Command directory updated to: '{}'", dir));
 						}
@@ -47,7 +70,24 @@ pub fn run(config: Option<Config>, args: &[String]) {
 					}
 				}
 			} else {
-				skip_n = skip_n - 1; // reduce skip
+				skip_n -= 1; // reduce skip
+			}
+		}
+		
+		if format == Format::Json && (!do_where || dir != "" || do_prompt || restore_opt.is_some()) {
+			return Err(PmanError::InvalidArgs(String::from("Only configure --where on its own has json output")));
+		}
+		
+		if let Some(n) = restore_opt {
+			Config::restore_backup(&location.path, n)?;
+			println!("Restored config backup {} to \"{}\"", n, location.path.display());
+			return Ok(()); // the restored config is the new config, nothing else to save
+		}
+		
+		if do_where {
+			print_where(config.is_some(), location, format)?;
+			if dir == "" && !do_prompt {
+				return Ok(()); // only asked where the config is, nothing to save
 			}
 		}
 		
@@ -65,13 +105,68 @@ pub fn run(config: Option<Config>, args: &[String]) {
 		}
 		
 		// persist config
-		if let Err(e) = new_cfg.write() {
-			println!("Failed to write configuration, {}", e);
-		}
+		new_cfg.write_to(&location.path)?;
+		return Ok(());
 	}
 }
 
 
+/// the config file in use and why it was picked, then every location which was searched for one in search order
+#[derive(Serialize)]
+struct WhereReport {
+	path: PathBuf,
+	exists: bool,
+	source: CfgSource,
+	candidates: Vec<Candidate>
+}
+
+#[derive(Serialize)]
+struct Candidate {
+	path: PathBuf,
+	source: CfgSource,
+	exists: bool,
+	/// true for the config file in use
+	picked: bool
+}
+
+fn print_where(exists: bool, location: &CfgLocation, format: Format) -> Result<(), PmanError> {
+	let mut report = WhereReport {
+		path: location.path.clone(),
+		exists: exists,
+		source: location.source,
+		candidates: Vec::new()
+	};
+	for candidate in Config::cfg_candidates(if location.source == CfgSource::Flag { Some(location.path.clone()) } else { None })? {
+		report.candidates.push(Candidate {
+			exists: candidate.path.exists(),
+			picked: &candidate == location,
+			path: candidate.path,
+			source: candidate.source
+		});
+	}
+	return output::print(format, &report);
+}
+
+impl Report for WhereReport {
+	fn print_text(&self) {
+		if self.exists {
+			println!("Using config \"{}\"", self.path.display());
+		} else {
+			println!("No config exists yet, it will be created at \"{}\"", self.path.display());
+		}
+		println!("  picked because its {}", self.source.describe());
+		
+		println!("Searched in order:");
+		for i in 0..self.candidates.len() {
+			let candidate = &self.candidates[i];
+			let mut ast = " ";
+			if candidate.picked { ast = "*" }
+			let state = if candidate.exists { "exists" } else { "missing" };
+			println!("{}  {}. {:<16} {} ({})", ast, i+1, candidate.source.to_string(), candidate.path.display(), state);
+		}
+	}
+}
+
 fn do_config_prompt(config: &mut Config) {
 	
 	let question = format!("Currently set to ({}): ", config.cmd_dir_str());
@@ -81,7 +176,7 @@ fn do_config_prompt(config: &mut Config) {
 			  You may press enter to leave the current value unchanged.");
 	
 	let mut new_dir = String::new();
-	while let None = utils::prompt_mut(&question, &mut new_dir, &config.cmd_dir_str()) {
+	while utils::prompt_mut(&question, &mut new_dir, config.cmd_dir_str()).is_none() {
 		println!("You must enter a value for the command directory.");
 	}
 	config.set_cmd_dir_str(new_dir);
@@ -0,0 +1,54 @@
+use std::env;
+
+use config::VERSIONS_FILE;
+use error::PmanError;
+use output::{self, Format, Report};
+use pman::{Manager, Selection, SelectionSource};
+
+pub const DESCRIPTION: &'static str = "Shows which group is used for each name in the current directory and why";
+
+pub fn print_usage() {
+	println!("Usage: pman current [name]\n\
+			 \0   name       (optional) Name of the configuration group to show, if ommited all names are shown\n\
+			 \0A name with its PMAN_<NAME> env var set, eg. PMAN_JAVA=170, uses the group it selects for that run.\n\
+			 \0Otherwise a name pinned by a {file} file in the current directory or above uses the pinned group,\n\
+			 \0and everything else the active group.", file=VERSIONS_FILE);
+}
+
+/// the selection of every name shown
+#[derive(Serialize)]
+struct CurrentReport {
+	selections: Vec<Selection>
+}
+
+pub fn run(manager: Manager, format: Format, args: &[String]) -> Result<(), PmanError> {
+	let dir = env::current_dir()?;
+	let selections;
+	if let Some(name) = args.first() {
+		selections = vec![manager.current_of(&dir, name)?];
+	} else {
+		selections = manager.current(&dir)?;
+	}
+	return output::print(format, &CurrentReport { selections: selections });
+}
+
+impl Report for CurrentReport {
+	fn print_text(&self) {
+		for selection in self.selections.iter() {
+			let group = match (selection.priority, selection.base_path.as_ref()) {
+				(Some(priority), Some(base_path)) => format!("{} - {}", priority, base_path),
+				_ => String::from("no group")
+			};
+			match selection.source {
+				SelectionSource::Env { ref var, ref selector } => {
+					println!("{} {}, overridden to {} by {}", selection.name, group, selector, var);
+				},
+				SelectionSource::Pinned { ref file, line, ref selector } => {
+					println!("{} {}, pinned to {} by \"{}\" line {}", selection.name, group, selector, file.display(), line);
+				},
+				SelectionSource::Active => println!("{} {}, the active group", selection.name, group),
+				SelectionSource::None => println!("{} {}, none is active", selection.name, group)
+			}
+		}
+	}
+}
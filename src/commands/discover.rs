@@ -0,0 +1,124 @@
+use std::env;
+use std::path::PathBuf;
+
+use config::{LinkGroup, Link};
+use error::PmanError;
+use pman::{Discovered, Manager};
+use utils;
+
+pub const DESCRIPTION: &'static str = "Finds installations in a directory and adds a group for each of them";
+
+pub fn print_usage() {
+	println!("Usage: pman discover <name> -in <dir> <args>\n\
+			 \0   name       (required) Name of the configuration group to add the groups to\n\
+			 \0Every directory in <dir> whose name matches the pattern becomes a group of name. Its version comes from\n\
+			 \0the directory name, eg. 1.7.0_79 for jdk1.7.0_79, and its priority from the version, eg. 10700.\n\
+			 \0Directories which are already groups, have no version in their name or are missing a link are skipped.\n\
+			 \0The groups to add are printed first and you are asked before anything is added.\n\
+			 \0possible args are:\n\
+			 \0   -in,--in <dir>\n\
+			 \0              (required) The directory the installations are in, eg. C:\\Program Files\\Java\n\
+			 \0   -pattern,--pattern <pattern>\n\
+			 \0              Only directories whose name matches are used, * matches anything and ? one character.\n\
+			 \0              Quote it so the shell doesnt expand it, eg. 'jdk*'. Every directory is used if not given\n\
+			 \0   -base,--base <path>\n\
+			 \0              Path inside each installation the links are relative to, eg. bin\n\
+			 \0   -link,--link <name> <path>\n\
+			 \0              Adds a link to every group, the path is relative to the base path. Can be repeated\n\
+			 \0   -like,--like <priority>\n\
+			 \0              Copies the links, link kind and env vars of an existing group of name, eg. -like 170\n\
+			 \0   -kind,--kind <kind>\n\
+			 \0              Sets the default kind of link made for the groups. Kinds are: {kinds}\n\
+			 \0   -dry-run,--dry-run\n\
+			 \0              Only prints the groups which would be added\n\
+			 \0   -force,-f  Adds the groups without asking", kinds=utils::link_kind_names());
+}
+
+pub fn run(mut manager: Manager, args: &[String]) -> Result<(), PmanError> {
+	if args.len() == 0 {
+		print_usage();
+		return Err(PmanError::InvalidArgs(String::from("Expected arguments <name> -in <dir>")));
+	}
+	let name = &args[0];
+	let mut dir_opt: Option<PathBuf> = None;
+	let mut pattern = String::from("*");
+	let mut template = LinkGroup::new(name.to_owned(), 0);
+	let mut like_opt: Option<String> = None;
+	let mut dry_run = false;
+	let mut force = false;
+	utils::loop_args(args[1..].iter(), |it, val| {
+		match val.to_ascii_lowercase().as_ref() {
+			"-in" | "--in" => dir_opt = Some(PathBuf::from(utils::next_arg(it, val, "<dir>")?)),
+			"-pattern" | "--pattern" => pattern = utils::next_arg(it, val, "<pattern>")?.to_owned(),
+			"-base" | "--base" => template.set_base_path(utils::next_arg(it, val, "<path>")?.to_owned()),
+			"-link" | "--link" => {
+				let lnk_name = utils::next_arg(it, val, "<name> <path>")?;
+				let lnk_path = utils::next_arg(it, val, "<name> <path>")?;
+				template.add_link(Link::new(lnk_name.to_owned(), lnk_path.to_owned()));
+			},
+			"-like" | "--like" => like_opt = Some(utils::next_arg(it, val, "<priority>")?.to_owned()),
+			"-kind" | "--kind" => template.kind = Some(utils::parse_link_kind(utils::next_arg(it, val, "<kind>")?)?),
+			"-dry-run" | "--dry-run" => dry_run = true,
+			"-f" | "-force" | "--force" => force = true,
+			_ => return Err(PmanError::InvalidArgs(format!("Unexpected or unknown argument '{}'", val)))
+		}
+		return Ok(());
+	})?;
+	let dir = dir_opt.ok_or_else(|| PmanError::InvalidArgs(String::from("Expected -in <dir>, the directory the installations are in")))?;
+	// the base paths of the groups are saved, they cant be relative to wherever this ran
+	let dir = env::current_dir()?.join(dir);
+
+	if let Some(like) = like_opt {
+		let selector = utils::parse_selector(&like, false)?;
+		let like_group = manager.config().select(name, &selector).ok_or_else(|| {
+			PmanError::MissingGroup {
+				name: name.to_owned(),
+				selector: selector.to_string()
+			}
+		})?;
+		// links given with -link win over the ones of the group
+		for link in like_group.links.iter() {
+			if template.get_link(&link.name).is_none() {
+				template.add_link(link.clone());
+			}
+		}
+		template.kind = template.kind.or(like_group.kind);
+		template.env = like_group.env.clone();
+	}
+	if template.links.is_empty() {
+		print_usage();
+		return Err(PmanError::InvalidArgs(String::from("Expected -link <name> <path> or -like <priority>, the groups would have no links")));
+	}
+
+	let plan = manager.discover(name, &dir, &pattern, &template)?;
+	let add_count = plan.iter().filter(|d| matches!(**d, Discovered::Add(_))).count();
+	println!("Found {} new group(s) of {} in \"{}\" matching {}", add_count, name, dir.display(), pattern);
+	for discovered in plan.iter() {
+		match *discovered {
+			Discovered::Add(ref group) => {
+				let version = group.version.as_ref().map_or(String::new(), |v| format!(" ({})", v));
+				println!("  + {}{} - {}", group.priority, version, group.base_path);
+			},
+			Discovered::Skip { ref dir, ref reason } => println!("    skipping \"{}\", {}", dir.display(), reason)
+		}
+	}
+	let links: Vec<String> = template.links.iter().map(|l| format!("{} => {}", l.name, l.path)).collect();
+	println!("Each group links {}{}", links.join(", "), template.kind.map_or(String::new(), |k| format!(" as {}", k.as_str())));
+
+	if add_count == 0 || dry_run {
+		return Ok(());
+	}
+	if !force {
+		if let Some(v) = utils::prompt(&String::from("You may press enter to say no.\nAdd these groups?\n(y/n): "), &String::from("n")) {
+			if !v.eq_ignore_ascii_case("y") {
+				return Ok(());
+			}
+		}
+	}
+	let activated = manager.add_discovered(&plan)?;
+	println!("Added {} group(s) of {}", add_count, name);
+	if let Some(group) = activated {
+		println!("Group {}-{} was activated, {} is in auto mode", group.name, group.priority, name);
+	}
+	return Ok(());
+}
@@ -0,0 +1,104 @@
+use error::PmanError;
+use output::{self, Format, Report};
+use pman::{Finding, Manager, Severity};
+use utils;
+
+pub const DESCRIPTION: &'static str = "Checks the config and the command directory for problems";
+
+pub fn print_usage() {
+	println!("Usage: pman doctor [args]\n\
+			 \0Looks for links whose target is gone, missing, stale or unknown files in the command directory,\n\
+			 \0links which collide and whether the command directory is on the PATH. Every finding comes with a\n\
+			 \0suggested fix. Exits with a non 0 status code if any errors were found.\n\
+			 \0possible args are:\n\
+			 \0   -fix,--fix Applies the safe fixes, missing links are made again and stale links pman made are removed.\n\
+			 \0              Files pman did not make are never touched");
+}
+
+/// every finding and what happened to its fix
+#[derive(Serialize)]
+struct DoctorReport {
+	findings: Vec<CheckedFinding>,
+	/// errors which are left, the exit code is non 0 when there are any
+	errors: usize,
+	/// findings which doctor --fix would fix
+	fixable: usize
+}
+
+#[derive(Serialize)]
+struct CheckedFinding {
+	#[serde(flatten)]
+	finding: Finding,
+	/// true when --fix fixed it
+	fixed: bool,
+	/// why --fix failed to fix it
+	fix_error: Option<String>
+}
+
+pub fn run(manager: Manager, format: Format, args: &[String]) -> Result<(), PmanError> {
+	let mut fix = false;
+	utils::loop_args(args.iter(), |_, val| {
+			match val.to_ascii_lowercase().as_ref() {
+				"-fix" | "--fix" => fix = true,
+				_ => {
+					// unknown argument
+					return Err(PmanError::InvalidArgs(format!("Unexpected or unknown argument '{}'", val)));
+				}
+			}
+			return Ok(());
+		})?;
+
+	let mut report = DoctorReport {
+		findings: Vec::new(),
+		errors: 0,
+		fixable: 0
+	};
+	for finding in manager.diagnose()? {
+		let mut checked = CheckedFinding {
+			finding: finding,
+			fixed: false,
+			fix_error: None
+		};
+		match checked.finding.fix {
+			Some(ref f) if fix => {
+				match manager.apply_fix(f) {
+					Ok(()) => checked.fixed = true,
+					Err(e) => checked.fix_error = Some(e.to_string())
+				}
+			},
+			Some(_) => report.fixable += 1,
+			None => {}
+		}
+		if checked.finding.severity == Severity::Error && !checked.fixed {
+			report.errors += 1;
+		}
+		report.findings.push(checked);
+	}
+
+	output::print(format, &report)?;
+	if report.errors > 0 {
+		return Err(PmanError::Unhealthy(report.errors));
+	}
+	return Ok(());
+}
+
+impl Report for DoctorReport {
+	fn print_text(&self) {
+		for checked in self.findings.iter() {
+			println!("{:<8}{}", checked.finding.severity, checked.finding.message);
+			if checked.fixed {
+				println!("        fixed");
+			} else if let Some(ref e) = checked.fix_error {
+				println!("        fix failed, {}", e);
+			} else {
+				println!("        fix: {}", checked.finding.suggestion);
+			}
+		}
+
+		if self.findings.is_empty() {
+			println!("No problems found");
+		} else if self.fixable > 0 {
+			println!("{} of the problems can be fixed with 'pman doctor --fix'", self.fixable);
+		}
+	}
+}
@@ -0,0 +1,53 @@
+use std::env;
+
+use config::{Shell, ENV_SCRIPT};
+use error::PmanError;
+use pman::Manager;
+use utils;
+
+pub const DESCRIPTION: &'static str = "Prints the env vars of the groups used in the current directory for a shell";
+
+pub fn print_usage() {
+	println!("Usage: pman env [name] <args>\n\
+			 \0   name       (optional) Name of the configuration group, if ommited the env vars of all groups are printed\n\
+			 \0The group is picked like 'pman current' does, so pins and PMAN_<NAME> overrides are honoured. The\n\
+			 \0output sets the vars in the shell, eg: eval \"$(pman env)\" in sh or pman env | Invoke-Expression in PowerShell.\n\
+			 \0The env vars of the active groups are also written to {script}.<ext> in the command directory\n\
+			 \0whenever a group is activated, shells can source those from their profile.\n\
+			 \0possible args are:\n\
+			 \0   -shell,-s <shell>\n\
+			 \0              Shell to print for, one of: {shells}. Guessed from the environment if not given.",
+		script=ENV_SCRIPT, shells=utils::shell_names());
+}
+
+pub fn run(manager: Manager, args: &[String]) -> Result<(), PmanError> {
+	let mut name_opt: Option<String> = None;
+	let mut shell = Shell::detect();
+	utils::loop_args(args.iter(), |it, val| {
+		match val.to_ascii_lowercase().as_ref() {
+			"-s" | "-shell" => {
+				shell = utils::parse_shell(utils::next_arg(it, val, "<shell>")?)?;
+			},
+			_ if name_opt.is_none() && !val.starts_with('-') => name_opt = Some(val.to_owned()),
+			_ => return Err(PmanError::InvalidArgs(format!("Unexpected or unknown argument '{}'", val)))
+		}
+		return Ok(());
+	})?;
+
+	let dir = env::current_dir()?;
+	let selections;
+	if let Some(name) = name_opt {
+		selections = vec![manager.current_of(&dir, &name)?];
+	} else {
+		selections = manager.current(&dir)?;
+	}
+	let config = manager.config();
+	for selection in selections.iter() {
+		let group = selection.priority.and_then(|pri| config.select_group(&selection.name, pri));
+		// vars of the other groups of the name are removed, they may still be set from before
+		for (var, val) in config.env_for(&selection.name, group.as_ref()) {
+			println!("{}", shell.apply_var(&var, val.as_ref()));
+		}
+	}
+	return Ok(());
+}
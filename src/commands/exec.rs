@@ -0,0 +1,69 @@
+use std::io;
+use std::process::ExitStatus;
+
+use error::PmanError;
+use pman::Manager;
+use utils;
+
+pub const DESCRIPTION: &'static str = "Runs a command with a group linked first on the PATH, without swapping to it";
+
+pub fn print_usage() {
+	println!("Usage: pman exec <name> <priority> [args] -- <command> <command args>\n\
+			 \0   name       (required) Name of the configuration group\n\
+			 \0   priority   (required) The group closest to but no less than this priority is used,\n\
+			 \0              eg: cfg.priority>=priority. For the highest priority pass -1. A version requirement\n\
+			 \0              like ^1.8, ~11 or >=17 uses the highest group of a matching version.\n\
+			 \0              Also takes an alias or one of lowest, active and previous, see 'pman help alias'.\n\
+			 \0   command    (required) The command to run, everything after -- is passed on as is\n\
+			 \0The links of the group are made in a temporary directory which is put first on the PATH of the command,\n\
+			 \0the active group and the command directory stay as they are. pman exits with the exit code of the command.\n\
+			 \0possible args are:\n\
+			 \0   -exact,-e  Uses exactly the given priority or version instead of the closest");
+}
+
+pub fn run(manager: Manager, args: &[String]) -> Result<(), PmanError> {
+	if args.len() < 2 {
+		print_usage();
+		return Err(PmanError::InvalidArgs(String::from("Expected arguments <name> <priority> -- <command>")));
+	}
+	let name = &args[0];
+	let mut exact = false;
+	let mut command_args = &args[args.len()..];
+	for i in 2..args.len() {
+		match args[i].to_ascii_lowercase().as_ref() {
+			"-e" | "-exact" => exact = true,
+			"--" => {
+				command_args = &args[i+1..];
+				break;
+			},
+			_ => return Err(PmanError::InvalidArgs(format!("Unexpected or unknown argument '{}', the command goes after --", args[i])))
+		}
+	}
+	if command_args.len() == 0 {
+		print_usage();
+		return Err(PmanError::InvalidArgs(String::from("Expected a command to run after --")));
+	}
+
+	let group_env = manager.group_env(name, &utils::parse_selector(&args[1], exact)?)?;
+	let status = group_env.command(&command_args[0]).args(&command_args[1..]).status().map_err(|e| {
+		io::Error::new(e.kind(), format!("Unable to run \"{}\", {}", command_args[0], e))
+	})?;
+	if !status.success() {
+		return Err(PmanError::Exited(exit_code(status)));
+	}
+	return Ok(());
+}
+
+/// exit code to pass on for status, commands killed by a signal get 128 + the signal like shells report them
+#[cfg(unix)]
+fn exit_code(status: ExitStatus) -> i32 {
+	use std::os::unix::process::ExitStatusExt;
+
+	return status.code().or_else(|| status.signal().map(|s| 128 + s)).unwrap_or(1);
+}
+
+/// exit code to pass on for status
+#[cfg(not(unix))]
+fn exit_code(status: ExitStatus) -> i32 {
+	return status.code().unwrap_or(1);
+}
@@ -1,8 +1,7 @@
 use utils;
-use config::{Config, LinkGroup, Link};
-use std;
-use std::path::PathBuf;
-use std::ascii::AsciiExt;
+use config::{GroupMode, LinkGroup, LinkSwap, Link, LinkKind, Version};
+use error::PmanError;
+use pman::{Manager, check_group_name};
 
 pub const DESCRIPTION: &'static str = "Adds, removes and modifies link groups.";
 
@@ -12,6 +11,8 @@ pub fn print_usage() {
 			 \0  priority (required) If not set you will be prompted to choose what group you wish to configure\n\
 			 \0           however if set then the a group closest to but no less than this param will be selected,\n\
 			 \0           eg: group.priority>=priority. For the highest priority pass -1, for the lowest pass 0.\n\
+			 \0           A version requirement like ^1.8, ~11 or >=17 selects the highest group of a matching version.\n\
+			 \0           Also takes an alias or one of lowest, active and previous, see 'pman help alias'.\n\
 			 \0possible args are:\n\
 			 \0  -exact,-e\n\
 			 \0           Forces group matching to require an exact match on the priority instead of best selection.\n\
@@ -20,9 +21,12 @@ pub fn print_usage() {
 			 \0           Adds a new link group if it doesnt exist with the <priority>, you will be prompted for\n\
 			 \0           confirmation if a group already exists at that same priority unless the -force param is present.\n\
 			 \0           Additionally using -add will cause priority matching to be exact instead of closest.\n\
+			 \0           The priority of a new group has to be a number. The first group of a name puts it in auto\n\
+			 \0           mode, so its activated right away.\n\
 			 \0  -remove\n\
 			 \0           Removes the group entirely, if this option has the highest priority. If this group was\n\
-			 \0           active the highest priority group will be automatically activated.\n\
+			 \0           active the highest priority group will be automatically activated and the name goes back\n\
+			 \0           to auto mode, see 'pman help mode'.\n\
 			 \n\
 			 \0  -dir,-d <path>\n\
 			 \0           The directory to set the new group to, not required to be specified but all link <path>'s\n\
@@ -34,6 +38,21 @@ pub fn print_usage() {
 			 \0           links path will be updated to match the <path> param.\n\
 			 \0  -unlink,-u <name>\n\
 			 \0           Removes a link from the group.\n\
+			 \0  -kind,-k <kind>\n\
+			 \0           Sets the default kind of link made for files in this group. Directories are always\n\
+			 \0           symlinked unless a link sets its own kind. Kinds are: {kinds}\n\
+			 \0  -link-kind,-lk <name> <kind>\n\
+			 \0           Sets the kind of link made for a single link of the group, overriding the group kind.\n\
+			 \0  -env <name> <value>\n\
+			 \0           Sets an env var while the group is active, ${{base}} in the value is replaced with the\n\
+			 \0           directory of the group, eg: -env JAVA_HOME '${{base}}/..'. See 'pman help env'.\n\
+			 \0  -unenv <name>\n\
+			 \0           Removes an env var from the group.\n\
+			 \0  -version <version>\n\
+			 \0           Sets the version of the group, eg: 1.8.0_20. Groups with a version are ordered by it instead of\n\
+			 \0           their priority, so the highest version is the highest group.\n\
+			 \0  -unversion\n\
+			 \0           Removes the version of the group, its ordered by priority again.\n\
 			 \n\
 			 \0  -force,-f\n\
 			 \0           Operates on the actions quietly and does not prompt for any confirmations.\n\
@@ -41,24 +60,21 @@ pub fn print_usage() {
 			 \0            - the [priority] argument is not present\n\
 			 \0            - no matching group could be matched\n\
 			 \0            - the group failed to be removed for other unspecfied reasons.\n\
-			 ");
+			 ", kinds=utils::link_kind_names());
 }
 
 
-pub fn run(mut config: &mut Config, args: &[String]) {
+pub fn run(mut manager: Manager, args: &[String]) -> Result<(), PmanError> {
 	if args.len() < 2 {
-		println!("Too few arguments for group command.");
 		print_usage();
+		return Err(PmanError::InvalidArgs(String::from("Too few arguments for group command.")));
 	} else {
 		
 		let mut args_itr = args.iter();
 		
 		// we know name and priority are there cause we did the check above. Another way to write this is to unwrap_or_else, but i dont think that adds much readability
 		let name = args_itr.next().unwrap();
-		let priority: i64 = args_itr.next().unwrap().parse::<i64>().unwrap_or_else(|_| {
-									println!("Invalid priority '{}', expected number.", args[1]);
-									std::process::exit(1);
-							}).clone();
+		let priority_arg = args_itr.next().unwrap();
 		let mut force = false;
 		let mut exact = false;
 		let mut remove = false;
@@ -66,8 +82,13 @@ pub fn run(mut config: &mut Config, args: &[String]) {
 		let mut new_dir_opt: Option<String> = None;
 		let mut new_links = Vec::<(String, String)>::new();
 		let mut rem_links = Vec::<String>::new();
+		let mut new_kind_opt: Option<LinkKind> = None;
+		let mut new_link_kinds = Vec::<(String, LinkKind)>::new();
+		let mut new_env = Vec::<(String, String)>::new();
+		let mut rem_env = Vec::<String>::new();
+		let mut new_version_opt: Option<Option<String>> = None; // Some(None) removes the version
 		
-		utils::loop_args(args_itr, |mut it, val| {
+		utils::loop_args(args_itr, |it, val| {
 				match val.to_ascii_lowercase().as_ref() {
 					"-f" | "-force" => {
 						force = true;
@@ -83,76 +104,97 @@ pub fn run(mut config: &mut Config, args: &[String]) {
 						exact = true; // exact matching
 					},
 					"-d" | "-dir" => {
-						new_dir_opt = Some(it.next().unwrap_or_else(|| {
-							println!("Invalid '{}' argument, expected <path> but ran out of arguments", val);
-							std::process::exit(1);
-						}).clone());
+						new_dir_opt = Some(utils::next_arg(it, val, "<path>")?.clone());
 					},
 					"-l" | "-link" => {
-						let name = it.next().unwrap_or_else(|| {
-							println!("Invalid '{}' argument, expected <name> <path> but ran out of arguments", val);
-							std::process::exit(1);
-						});
-						let path = it.next().unwrap_or_else(|| {
-							println!("Invalid '{}' argument, expected <name> <path> but ran out of arguments", val);
-							std::process::exit(1);
-						});
+						let name = utils::next_arg(it, val, "<name> <path>")?;
+						let path = utils::next_arg(it, val, "<name> <path>")?;
 						new_links.push((name.to_owned(), path.to_owned()));
 					},
 					"-u" | "-unlink" => {
-						let name = it.next().unwrap_or_else(|| {
-							println!("Invalid '{}' argument, expected <name> but ran out of arguments", val);
-							std::process::exit(1);
-						});
+						let name = utils::next_arg(it, val, "<name>")?;
 						rem_links.push(name.to_owned());
 					},
+					"-k" | "-kind" => {
+						let kind = utils::next_arg(it, val, "<kind>")?;
+						new_kind_opt = Some(utils::parse_link_kind(kind)?);
+					},
+					"-lk" | "-link-kind" => {
+						let name = utils::next_arg(it, val, "<name> <kind>")?;
+						let kind = utils::next_arg(it, val, "<name> <kind>")?;
+						new_link_kinds.push((name.to_owned(), utils::parse_link_kind(kind)?));
+					},
+					"-env" => {
+						let name = utils::next_arg(it, val, "<name> <value>")?;
+						let value = utils::next_arg(it, val, "<name> <value>")?;
+						if !is_var_name(name) {
+							return Err(PmanError::InvalidArgs(format!("Invalid env var name '{}', use letters, digits and _", name)));
+						}
+						new_env.push((name.to_owned(), value.to_owned()));
+					},
+					"-unenv" => {
+						let name = utils::next_arg(it, val, "<name>")?;
+						rem_env.push(name.to_owned());
+					},
+					"-version" => {
+						let version = utils::next_arg(it, val, "<version>")?;
+						if Version::parse(version).is_none() {
+							return Err(PmanError::InvalidArgs(format!("Invalid version '{}', expected eg. 1.8.0, 17 or 1.7.0_79", version)));
+						}
+						new_version_opt = Some(Some(version.trim().to_owned()));
+					},
+					"-unversion" => {
+						new_version_opt = Some(None);
+					},
 					_ => {
 						// unknown argument
-						println!("Unexpected or unknown argument '{}'", val);
-						std::process::exit(1);
+						return Err(PmanError::InvalidArgs(format!("Unexpected or unknown argument '{}'", val)));
 					}
 				}
-			});
-		// skip_n
-		let mut link_group_opt: Option<LinkGroup>;
-		if exact {
-			link_group_opt = config.select_group(&name, priority as u64);
-		} else {
-			if priority < 0 {
-				link_group_opt = config.select_highest_group(&name);
-			} else {
-				link_group_opt = config.select_closest_group(&name, priority as u64);
-			}
-		}
+				return Ok(());
+			})?;
+		let mut link_group_opt = manager.config().select(name, &utils::parse_selector(priority_arg, exact)?);
+		let mut cmd_dir = manager.link_cmd_dir()?;
+		let config = manager.config_mut();
+		let mut swap_opt: Option<LinkSwap> = None; // refreshes the links of the group if its active
 		
 		let did_create; // yuk, i dont like this
 		if add {
 			// uhoh existing group exists, prompt for removal?
-			if !force && link_group_opt.is_some() {
-				let link_group = link_group_opt.as_ref().unwrap();
+			if let (false, Some(link_group)) = (force, link_group_opt.as_ref()) {
 				let prompt_res = utils::prompt(&format!("You may press enter to say no.\nReplace existing group {}/{}?\n(y/n): ", link_group.name, link_group.priority), &String::from("n"));
 				if let Some(v) = prompt_res {
 					if !v.eq_ignore_ascii_case("y") {
 						// dont continue, just exit
-						std::process::exit(0);
+						return Ok(());
 					}
 				}
 			}
 			// create  a new group!
-			link_group_opt = Some(LinkGroup::new(name.to_owned(), priority as u64));
+			check_group_name(name)?;
+			let priority = priority_arg.parse::<u64>().map_err(|_| {
+				PmanError::InvalidArgs(format!("Invalid priority '{}', a new group needs a number.", priority_arg))
+			})?;
+			link_group_opt = Some(LinkGroup::new(name.to_owned(), priority));
 			did_create = true;
+			// the first group of a name starts it in auto mode, so its activated once its set up
+			if config.config_entrys_by_name(name).is_none_or(|v| v.is_empty()) {
+				config.set_mode(name, GroupMode::Auto);
+			}
 		} else {
 			did_create = false;
 		}
 		
 		match link_group_opt {
 			None => {
-				println!("No matching link group found for {}/{}", name, priority);
-				std::process::exit(1);
+				return Err(PmanError::MissingGroup {
+					name: name.to_owned(),
+					selector: priority_arg.to_owned()
+				});
 			},
 			Some(mut link_group) => {
 				if did_create {
-					println!("Created new group {}/{}", name, priority);
+					println!("Created new group {}/{}", name, link_group.priority);
 				} else {
 					println!("Matched to group {}/{}", link_group.name, link_group.priority);
 				}
@@ -162,37 +204,35 @@ pub fn run(mut config: &mut Config, args: &[String]) {
 						if let Some(v) = utils::prompt(&format!("You may press enter to say no.\nRemove group {}/{}?\n(y/n): ", link_group.name, link_group.priority), &String::from("n")) {
 							if !v.eq_ignore_ascii_case("y") {
 								// dont continue, just exit
-								std::process::exit(0);
+								return Ok(());
 							}
 						}
 					}
 					
 					
-					if config.is_active(&link_group) {
-						// deactivate
-						link_group.deactivate(&config.cmd_dir());
+					let was_active = config.is_active(&link_group);
+					let new_group_opt = manager.remove_group(&link_group.name, link_group.priority)?;
+					if was_active {
 						println!("Group {}-{} was deactivated", link_group.name, link_group.priority);
 					}
-					config.remove_config_entry(&link_group.name, link_group.priority);
-					
-					if let Some(new_group) = config.select_highest_group(&name) {
-						new_group.activate(&config.cmd_dir());
+					println!("Group {}-{} was deleted", link_group.name, link_group.priority);
+					if let Some(new_group) = new_group_opt {
 						println!("Group {}-{} was activated in response", new_group.name, new_group.priority);
 					}
-					println!("Group {}-{} was deleted", link_group.name, link_group.priority);
 				} else {
 					// not deleted, just modifying or adding
-					let cmd_dir = config.cmd_dir();
-					let cfg_source_path = PathBuf::from(&link_group.base_path);
-					// do removing of links
+					for (lnk_name, _) in new_links.iter() {
+						if config.has_link(lnk_name) {
+							return Err(PmanError::LinkConflict {
+								name: lnk_name.to_owned(),
+								reason: String::from("a standalone link has the same name")
+							});
+						}
+					}
+					// do removing of links, the files go when the links are refreshed below
 					for lnk_name in rem_links.iter() {
-						if let Some(lnk) = link_group.get_link(lnk_name) {
-							// try and remove it, remove it right away instead of later
-							if let Err(e) = lnk.remove_link(&cmd_dir, &cfg_source_path) {
-								println!("Error while removing link on file system \"{}\", {}.", lnk.name, e);
-							}
-							println!("Removing link \"{}\"", lnk.name);
-							link_group.remove_link(lnk_name);
+						if link_group.remove_link(lnk_name) {
+							println!("Removing link \"{}\"", lnk_name);
 						} else {
 							// no link found by that name, i dont think we want to fail or anything
 						}
@@ -200,12 +240,48 @@ pub fn run(mut config: &mut Config, args: &[String]) {
 					
 					// do adding of links
 					// TODO: learn what the fuck are these 'ref' things needed for in this loop, & doesnt work. I had thought ref==& but i guess im wrong
-					for &(ref lnk_name, ref lnk_path) in new_links.iter() {
+					for (lnk_name, lnk_path) in new_links.iter() {
 						println!("Adding link \"{}\" => \"{}\"", lnk_name, lnk_path);
 						let new_link = Link::new(lnk_name.to_owned(), lnk_path.to_owned());
 						link_group.add_link(new_link);
 					}
 					
+					// update link kinds
+					if let Some(new_kind) = new_kind_opt {
+						println!("Setting link kind \"{}\"", new_kind.as_str());
+						link_group.kind = Some(new_kind);
+					}
+					for (lnk_name, lnk_kind) in new_link_kinds.iter() {
+						if let Some(lnk) = link_group.links.iter_mut().find(|l| l.name.eq(lnk_name)) {
+							println!("Setting link kind of \"{}\" to \"{}\"", lnk_name, lnk_kind.as_str());
+							lnk.kind = Some(*lnk_kind);
+						} else {
+							println!("No link named \"{}\" in the group, its kind was not set", lnk_name);
+						}
+					}
+					
+					// update env vars
+					for env_name in rem_env.iter() {
+						if link_group.env.remove(env_name).is_some() {
+							println!("Removing env var {}", env_name);
+						} else {
+							println!("No env var named {} in the group", env_name);
+						}
+					}
+					for (env_name, env_val) in new_env.iter() {
+						println!("Setting env var {}={}", env_name, env_val);
+						link_group.env.insert(env_name.to_owned(), env_val.to_owned());
+					}
+					
+					// update version
+					if let Some(new_version) = new_version_opt {
+						match new_version {
+							Some(ref version) => println!("Setting version {}", version),
+							None => println!("Removing version")
+						}
+						link_group.version = new_version;
+					}
+					
 					// update directory
 					if let Some(new_dir) = new_dir_opt {
 						println!("Setting base directory \"{}\"", new_dir);
@@ -214,26 +290,46 @@ pub fn run(mut config: &mut Config, args: &[String]) {
 					
 					if config.is_active(&link_group) {
 						println!("Group is active, refreshing links to make everything up to date.");
-						link_group.activate(&cmd_dir);
+						// swapped from the links as they are now, so links which were removed or whos file name changes
+						// with their kind are cleaned up. If anything fails the old links stay
+						let mut swap = LinkSwap::stage(&cmd_dir, config.active_config_entry(&link_group.name), &link_group)?;
+						swap.commit(&mut cmd_dir)?;
+						swap_opt = Some(swap);
 					}
 				
 					// TODO: I kinda stink at these borrows, make it so i am mutating the underlying entry which has already been added, or make a nice method which replaces the entry
 					// remove before insert, therefore there should be no problems.
 					config.remove_config_entry(&link_group.name, link_group.priority);
-					if !config.insert_config_entry(link_group) {
-						panic!("Could not insert config entry due to duplicate entry. You should never see this message");
-					}
+					config.insert_config_entry(link_group)?;
 				}
 			}
 		}
 		
-		// finally save the configuration
-		if let Err(e) = config.write() {
-			println!("Failed to write to configuration file, {}. Group was not removed", e);
+		// finally save the configuration, the refreshed links are undone if that fails
+		if let Err(e) = manager.save() {
+			if let Some(mut swap) = swap_opt {
+				return Err(swap.rollback_after(&mut cmd_dir, e));
+			}
+			return Err(e);
 		}
-		
+		if let Some(swap) = swap_opt {
+			// failing to clean up only leaves the old links behind in the work directory
+			let _ = swap.finish();
+		}
+		// a name in auto mode keeps its highest group active, adding or changing a group may change which one that is
+		if let Some(new_group) = manager.apply_mode(name)? {
+			println!("Group {}-{} was activated, {} is in auto mode", new_group.name, new_group.priority, name);
+		}
+		// the active groups may have changed, or the env vars of one
+		return manager.write_env_scripts();
 	}
 }
 
+/// checks name can be used as env var name in every shell
+fn is_var_name(name: &str) -> bool {
+	return !name.is_empty() && !name.starts_with(|c: char| c.is_ascii_digit())
+		&& name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+}
+
 
 
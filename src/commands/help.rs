@@ -1,33 +1,76 @@
 use commands::*;
-use std::ascii::AsciiExt;
+use error::PmanError;
+use config;
 
 pub const DESCRIPTION: &'static str = "Displays help text for all commands or individual commands";
 
 pub fn print_help() {
 	// TODO: uhh this way of printing text kinda stinks, i guess theres a nice macro called indoc which looks good. IDK tho
-	println!("Usage: pman [command] <args>\n\
+	println!("Usage: pman [global options] [command] <args>\n\
+			  global options are:\n\
+			  \0   --cfg <path>  Config file to use for this run instead of the one next to pman, or set {cfg_env}\n\
+			  \0   --cmd <dir>   Command directory to use for this run instead of the configured one, or set {cmd_env}\n\
+			  \0   --force       Replace and remove files in the command directory pman did not make or which were changed since\n\
+			  \0   --format <f>  Output format of list, which, doctor, current and configure --where, text (the default) or json\n\
 			  command options are:\n\
 			  \0   configure  {configure}\n\
 			  \0   list       {list}\n\
 			  \0   group      {group}\n\
+			  \0   discover   {discover}\n\
 			  \0   swap       {swap}\n\
+			  \0   profile    {profile}\n\
+			  \0   alias      {alias}\n\
+			  \0   mode       {mode}\n\
 			  \0   link       {link}\n\
 			  \0   rebuild    {rebuild}\n\
+			  \0   doctor     {doctor}\n\
+			  \0   which      {which}\n\
+			  \0   pin        {pin}\n\
+			  \0   current    {current}\n\
+			  \0   exec       {exec}\n\
+			  \0   env        {env}\n\
+			  \0   init       {init}\n\
 			  \0   help       {help}\n\
 			  \n\
 			  Many commands have additional arguments available to them.\n\
-			  you may use 'pman help [command]' to display help specfically for a command.",
+			  you may use 'pman help [command]' to display help specfically for a command.\n\
+			  \n\
+			  Exit codes:\n\
+			  \0   0  success\n\
+			  \0   1  I/O error\n\
+			  \0   2  invalid command line usage\n\
+			  \0   3  pman is not configured yet\n\
+			  \0   4  the configuration file could not be parsed\n\
+			  \0   5  no group or link matched\n\
+			  \0   6  a group already exists with that priority\n\
+			  \0   7  a link conflicts with another link or a file pman does not own\n\
+			  \0   8  another pman is running and holds the config lock\n\
+			  \0   9  pman doctor found errors\n\
+			  pman exec exits with the exit code of the command it ran instead.",
+		cfg_env=config::CONFIG_ENV,
+		cmd_env=config::CMD_DIR_ENV,
 		configure=configure::DESCRIPTION,
 		list=list::DESCRIPTION,
 		group=group::DESCRIPTION,
+		discover=discover::DESCRIPTION,
 		link=link::DESCRIPTION,
 		swap=swap::DESCRIPTION,
+		profile=profile::DESCRIPTION,
+		alias=alias::DESCRIPTION,
+		mode=mode::DESCRIPTION,
 		rebuild=rebuild::DESCRIPTION,
+		doctor=doctor::DESCRIPTION,
+		which=which::DESCRIPTION,
+		pin=pin::DESCRIPTION,
+		current=current::DESCRIPTION,
+		exec=exec::DESCRIPTION,
+		env=env::DESCRIPTION,
+		init=init::DESCRIPTION,
 		help=help::DESCRIPTION
 	);
 }
 
-pub fn run(args: &[String]) {
+pub fn run(args: &[String]) -> Result<(), PmanError> {
 	if args.len() == 0 {
 		print_help();
 	} else {
@@ -46,11 +89,45 @@ pub fn run(args: &[String]) {
 		} else if "rebuild".eq_ignore_ascii_case(&args[0]) {
 			println!("Description: {}", rebuild::DESCRIPTION);
 			rebuild::print_usage();
+		} else if "doctor".eq_ignore_ascii_case(&args[0]) {
+			println!("Description: {}", doctor::DESCRIPTION);
+			doctor::print_usage();
+		} else if "which".eq_ignore_ascii_case(&args[0]) {
+			println!("Description: {}", which::DESCRIPTION);
+			which::print_usage();
+		} else if "pin".eq_ignore_ascii_case(&args[0]) {
+			println!("Description: {}", pin::DESCRIPTION);
+			pin::print_usage();
+		} else if "current".eq_ignore_ascii_case(&args[0]) {
+			println!("Description: {}", current::DESCRIPTION);
+			current::print_usage();
+		} else if "exec".eq_ignore_ascii_case(&args[0]) {
+			println!("Description: {}", exec::DESCRIPTION);
+			exec::print_usage();
+		} else if "env".eq_ignore_ascii_case(&args[0]) {
+			println!("Description: {}", env::DESCRIPTION);
+			env::print_usage();
+		} else if "init".eq_ignore_ascii_case(&args[0]) {
+			println!("Description: {}", init::DESCRIPTION);
+			init::print_usage();
+		} else if "profile".eq_ignore_ascii_case(&args[0]) {
+			println!("Description: {}", profile::DESCRIPTION);
+			profile::print_usage();
+		} else if "alias".eq_ignore_ascii_case(&args[0]) {
+			println!("Description: {}", alias::DESCRIPTION);
+			alias::print_usage();
+		} else if "mode".eq_ignore_ascii_case(&args[0]) {
+			println!("Description: {}", mode::DESCRIPTION);
+			mode::print_usage();
+		} else if "discover".eq_ignore_ascii_case(&args[0]) {
+			println!("Description: {}", discover::DESCRIPTION);
+			discover::print_usage();
 		} else if "swap".eq_ignore_ascii_case(&args[0]) {
 			println!("Description: {}", swap::DESCRIPTION);
 			swap::print_usage();
 		} else {
-			println!("Unkown help target \"{}\"", args[0]);
+			return Err(PmanError::InvalidArgs(format!("Unkown help target \"{}\"", args[0])));
 		}
 	}
+	return Ok(());
 }
\ No newline at end of file
@@ -0,0 +1,34 @@
+use std::env;
+
+use config::Shell;
+use error::PmanError;
+use pman::Manager;
+use utils;
+
+pub const DESCRIPTION: &'static str = "Prints the snippet which sets a shell up for pman, for its rc file";
+
+pub fn print_usage() {
+	println!("Usage: pman init [shell]\n\
+			 \0   shell      (optional) One of: bash, zsh, {shells}. Guessed from the environment if not given\n\
+			 \0The snippet puts the command directory first on the PATH unless its already there, sets the env vars\n\
+			 \0of the active groups and wraps pman in a function so a swap takes effect in the shell right away.\n\
+			 \0Add it to the rc file of the shell, either the output itself or a line which runs pman init:\n\
+			 \0   bash/zsh     eval \"$(pman init bash)\"\n\
+			 \0   fish         pman init fish | source\n\
+			 \0   powershell   pman init powershell | Out-String | Invoke-Expression\n\
+			 \0   cmd          pman init cmd > pman-init.cmd, then run it from the AutoRun setting of cmd",
+		shells=utils::shell_names());
+}
+
+pub fn run(manager: Manager, args: &[String]) -> Result<(), PmanError> {
+	if args.len() > 1 {
+		print_usage();
+		return Err(PmanError::InvalidArgs(String::from("Expected at most one argument [shell]")));
+	}
+	let shell = match args.first() {
+		Some(name) => utils::parse_shell(name)?,
+		None => Shell::detect()
+	};
+	print!("{}", shell.init_script(&manager.config().cmd_dir(), &env::current_exe()?));
+	return Ok(());
+}
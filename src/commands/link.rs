@@ -1,8 +1,8 @@
-use config::Config;
 use config::Link;
+use config::LinkKind;
+use error::PmanError;
+use pman::Manager;
 use utils;
-use std;
-use std::ascii::AsciiExt;
 
 pub const DESCRIPTION: &'static str = "Installs a new link, a singular connection from one file to another (or dir)";
 
@@ -16,43 +16,41 @@ pub fn print_usage() {
  			  \0              all actions will be void and the application will exit with a non 0 status code.\n\
 			  \0   -unlink,-u <name> \n\
 			  \0              Removes a link by name, if no link by that name exists then this operation does nothing.\n\
+			  \0   -kind,-k <kind>\n\
+			  \0              Kind of link to make for every -link after this argument. Kinds are: {kinds}\n\
 			  \0              \n\
-			  ");
+			  ", kinds=utils::link_kind_names());
 }
 
-pub fn run(mut config: Config, args: &[String]) {
+pub fn run(mut manager: Manager, args: &[String]) -> Result<(), PmanError> {
 	if args.len() < 1 {
 		print_usage();
-		std::process::exit(1);
+		return Err(PmanError::InvalidArgs(String::from("Too few arguments for link command.")));
 	}
 	
-	utils::loop_args(args.iter(), |mut it, val| {
+	let mut kind_opt: Option<LinkKind> = None;
+	utils::loop_args(args.iter(), |it, val| {
 			match val.to_ascii_lowercase().as_ref() {
 				"-l" | "-link" => {
-					let name = it.next().unwrap_or_else(|| {
-						println!("Invalid '{}' argument, expected <name> <path> but ran out of arguments", val);
-						std::process::exit(1);
-					});
-					let path = it.next().unwrap_or_else(|| {
-						println!("Invalid '{}' argument, expected <name> <path> but ran out of arguments", val);
-						std::process::exit(1);
-					});
-					add_link(&mut config, name, path);
+					let name = utils::next_arg(it, val, "<name> <path>")?;
+					let path = utils::next_arg(it, val, "<name> <path>")?;
+					add_link(&mut manager, name, path, kind_opt)?;
+				},
+				"-k" | "-kind" => {
+					let kind = utils::next_arg(it, val, "<kind>")?;
+					kind_opt = Some(utils::parse_link_kind(kind)?);
 				},
 				"-u" | "-unlink" => {
-					let name = it.next().unwrap_or_else(|| {
-						println!("Invalid '{}' argument, expected <name> <path> but ran out of arguments", val);
-						std::process::exit(1);
-					});
-					remove_link(&mut config, name);
+					let name = utils::next_arg(it, val, "<name>")?;
+					remove_link(&mut manager, name)?;
 				},
 				_ => {
 					// unknown argument
-					println!("Unexpected or unknown argument '{}'", val);
-					std::process::exit(1);
+					return Err(PmanError::InvalidArgs(format!("Unexpected or unknown argument '{}'", val)));
 				}
 			}
-		});
+			return Ok(());
+		})?;
 //		
 //	let cmd = &args[0];
 //	
@@ -76,65 +74,34 @@ pub fn run(mut config: Config, args: &[String]) {
 //		std::process::exit(1);
 //	}
 	
+	return Ok(());
 }
 
-fn remove_link(config: &mut Config, name: &String) {
-	let exit_status: i32;
-	let cmd_dir = config.cmd_dir();
-	if let Some(link) = config.get_link(&name) {
-		match link.remove_link(&cmd_dir, &cmd_dir) {
-			Err(e) => {
-				println!("Failed to remove link \"{}\". {}", name, e);
-				exit_status = 1;
-			},
-			Ok(r_path) => {
-				println!("Link removed \"{}\" => \"{}\"", name, r_path.to_str().unwrap_or("<unknown>"));
-				exit_status = 0;
-			}
+fn remove_link(manager: &mut Manager, name: &String) -> Result<(), PmanError> {
+	match manager.remove_link(name) {
+		Err(e) => {
+			println!("Failed to remove link \"{}\".", name);
+			return Err(e);
+		},
+		Ok(r_path) => {
+			println!("Link removed \"{}\" => \"{}\"", name, r_path.to_str().unwrap_or("<unknown>"));
+			return Ok(());
 		}
-	} else {
-		println!("No link named \"{}\" found", name);
-		exit_status = 1;
-	}
-	
-	config.remove_link(name);
-	if let Err(e) = config.write() {
-		println!("Failed to persist data, link was removed but config couldnt be saved! {}", e);
-		std::process::exit(1);
 	}
-	std::process::exit(exit_status);
 }
 
 
-fn add_link(config: &mut Config, name: &String, path: &String) {
-	let cmd_dir = config.cmd_dir();
-	
-	if config.has_link(&name) {
-		// try and remove the old link, only println when failed
-		// should always be Some() due to our previous check
-		if let Some(link) = config.get_link(&name) {
-			if let Err(e) = link.remove_link(&cmd_dir, &cmd_dir) {
-				println!("Failed to remove link \"{}\". {}", name, e);
-			}
-		}
-		
-		config.remove_link(name); // remove the link from the config
-	}
-	
-	let link = Link::new(name.clone(), path.clone());
-	match link.create_link(&cmd_dir, &cmd_dir) {
+fn add_link(manager: &mut Manager, name: &String, path: &String, kind: Option<LinkKind>) -> Result<(), PmanError> {
+	let mut link = Link::new(name.clone(), path.clone());
+	link.kind = kind;
+	match manager.add_link(link) {
 		Err(e) => {
-			println!("Failed to create link, aborting. {}", e);
-			std::process::exit(1);
+			println!("Failed to create link, aborting.");
+			return Err(e);
 		},
 		Ok(r_path) => {
-			println!("Link created \"{}\" => \"{}\"", link.name, r_path.to_str().unwrap_or("<unknown>"));
+			println!("Link created \"{}\" => \"{}\"", name, r_path.to_str().unwrap_or("<unknown>"));
+			return Ok(());
 		}
 	}
-	
-	config.add_link(link);
-	if let Err(e) = config.write() {
-		println!("Failed to persist data, link was created but config couldnt be saved! {}", e);
-		std::process::exit(1);
-	}
-}
\ No newline at end of file
+}
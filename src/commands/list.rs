@@ -1,5 +1,10 @@
-use config::Config;
-use config::LinkGroup;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use config::{GroupMode, Link, LinkKind};
+use error::PmanError;
+use output::{self, Format, Report};
+use pman::Manager;
 
 pub const DESCRIPTION: &'static str = "Lists registered links and configuration groups";
 
@@ -9,37 +14,134 @@ pub fn print_usage() {
 			 \0              if ommited then all link groups and standalone links will be listed");
 }
 
-pub fn run(config: Config, args: &[String]) {
-	
-	if args.len() == 0 { // list everything
-		println!("{} standalone link(s)", config.links().len());
+/// everything list shows, links is empty when only the groups of one name are listed
+#[derive(Serialize)]
+struct ListReport {
+	#[serde(skip)]
+	everything: bool, // false when only the groups of one name are listed
+	cmd_dir: PathBuf,
+	links: Vec<LinkReport>,
+	/// mode of every name whose groups are listed
+	modes: BTreeMap<String, GroupMode>,
+	groups: Vec<GroupReport>
+}
+
+#[derive(Serialize)]
+struct GroupReport {
+	name: String,
+	priority: u64,
+	/// the version of the group as written in the config, None if it has none
+	version: Option<String>,
+	aliases: Vec<String>,
+	base_path: String,
+	active: bool,
+	/// default kind of the groups links, None is the platform default
+	kind: Option<LinkKind>,
+	/// env vars of the group with ${base} replaced
+	env: BTreeMap<String, String>,
+	links: Vec<LinkReport>
+}
+
+#[derive(Serialize)]
+struct LinkReport {
+	name: String,
+	/// the path as its written in the config, may be relative to the groups base path
+	path: String,
+	/// absolute path of the file the link points at
+	target: PathBuf,
+	/// the kind of link made for it after falling back to the groups kind and the platform default
+	kind: LinkKind
+}
+
+impl LinkReport {
+	fn new(link: &Link, cmd_dir: &PathBuf, source_dir: &PathBuf, group_kind: Option<LinkKind>) -> LinkReport {
+		let (_, target, kind) = link.rel_link(cmd_dir, source_dir, group_kind);
+		return LinkReport {
+			name: link.name.to_owned(),
+			path: link.path.to_owned(),
+			target: target,
+			kind: kind
+		};
+	}
+}
+
+pub fn run(manager: Manager, format: Format, args: &[String]) -> Result<(), PmanError> {
+	let config = manager.config();
+	let cmd_dir = config.cmd_dir();
+	let name = args.first();
+
+	let mut report = ListReport {
+		everything: name.is_none(),
+		cmd_dir: cmd_dir.clone(),
+		links: Vec::new(),
+		modes: BTreeMap::new(),
+		groups: Vec::new()
+	};
+	if name.is_none() {
 		for link in config.links() {
+			report.links.push(LinkReport::new(link, &cmd_dir, &cmd_dir, None));
+		}
+	}
+	for entry in manager.list(name)? {
+		let base_path = PathBuf::from(&entry.base_path);
+		report.modes.insert(entry.name.to_owned(), config.mode(&entry.name));
+		report.groups.push(GroupReport {
+			name: entry.name.to_owned(),
+			priority: entry.priority,
+			version: entry.version.to_owned(),
+			aliases: entry.aliases.to_owned(),
+			base_path: entry.base_path.to_owned(),
+			active: config.is_active(entry),
+			kind: entry.kind,
+			env: entry.env_vars().into_iter().collect(),
+			links: entry.links.iter().map(|l| LinkReport::new(l, &cmd_dir, &base_path, entry.kind)).collect()
+		});
+	}
+	return output::print(format, &report);
+}
+
+impl Report for ListReport {
+	fn print_text(&self) {
+		if !self.everything { // just entries related to 1st argument
+			for (name, mode) in self.modes.iter() {
+				println!("  {} - {} mode", name, mode.as_str());
+			}
+			for group in self.groups.iter() {
+				group.print_text();
+			}
+			return;
+		}
+
+		println!("{} standalone link(s)", self.links.len());
+		for link in self.links.iter() {
 			println!("   {} - {}", link.name, link.path);
 		}
-		println!("{} link group(s), * asterisk mark active groups", config.config_map().len());
-		let mut key_set: Vec<_> = config.config_map().iter().collect();
-		key_set.sort_by(|&(a, _), &(b, _)| a.cmp(&b));
-		
-		for kv in key_set {
-			let (key, _) = kv;	
-			println!("  {}", key);
-			list_entrys(&config, &key);
+		let mut names: Vec<&String> = self.groups.iter().map(|g| &g.name).collect();
+		names.dedup();
+		println!("{} link group(s), * asterisk mark active groups", names.len());
+
+		let mut last_name: Option<&String> = None;
+		for group in self.groups.iter() {
+			if last_name != Some(&group.name) {
+				println!("  {} - {} mode", group.name, self.modes[&group.name].as_str());
+				last_name = Some(&group.name);
+			}
+			group.print_text();
 		}
-	} else {
-		list_entrys(&config, &args[0]); // just entries related to 1st argument
 	}
 }
 
-fn list_entrys(config: &Config, name:&String) {
-	let n_cfg:Option<&Vec<LinkGroup>> = config.config_entrys_by_name(&name);
-	match n_cfg {
-		Some(cfg_vec) => {
-			for entry in cfg_vec {
-				let mut ast = " ";
-				if config.is_active(entry) { ast = "*" }
-				println!("    {} {} - {}", ast, entry.priority, entry.base_path)
-			}
-		},
-		None => println!("No configs found for '{}'", name)
+impl GroupReport {
+	fn print_text(&self) {
+		let mut ast = " ";
+		if self.active { ast = "*" }
+		let mut label = self.priority.to_string();
+		if let Some(ref version) = self.version {
+			label.push_str(&format!(" ({})", version));
+		}
+		if !self.aliases.is_empty() {
+			label.push_str(&format!(" [{}]", self.aliases.join(", ")));
+		}
+		println!("    {} {} - {}", ast, label, self.base_path)
 	}
-}
\ No newline at end of file
+}
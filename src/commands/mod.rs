@@ -4,4 +4,15 @@ pub mod list;
 pub mod swap;
 pub mod link;
 pub mod rebuild;
+pub mod doctor;
+pub mod which;
+pub mod pin;
+pub mod current;
+pub mod exec;
+pub mod env;
+pub mod init;
+pub mod profile;
+pub mod alias;
+pub mod mode;
+pub mod discover;
 pub mod help;
\ No newline at end of file
@@ -0,0 +1,48 @@
+use config::GroupMode;
+use error::PmanError;
+use pman::Manager;
+
+pub const DESCRIPTION: &'static str = "Shows or sets if the active group of a name is picked automatically or by hand";
+
+pub fn print_usage() {
+	println!("Usage: pman mode [name] [mode]\n\
+			 \0   name       (optional) Name of the configuration group, if ommited the mode of every name is listed\n\
+			 \0   mode       (optional) auto or manual, if ommited the mode of name is shown\n\
+			 \0In auto mode the highest group of the name is kept active, adding, changing or removing groups with\n\
+			 \0'pman group' swaps to whichever group is highest then. Setting auto mode swaps to it right away.\n\
+			 \0In manual mode the group picked with swap stays active. Swapping or using a profile puts a name in\n\
+			 \0manual mode, removing the group picked by hand puts it back in auto mode and activates the highest group.\n\
+			 \0Names get auto mode when their first group is added, names set up before modes existed are in manual mode.");
+}
+
+pub fn run(mut manager: Manager, args: &[String]) -> Result<(), PmanError> {
+	if args.len() > 2 {
+		print_usage();
+		return Err(PmanError::InvalidArgs(String::from("Expected at most the arguments [name] [mode]")));
+	}
+	if args.len() == 0 {
+		let config = manager.config();
+		let mut names: Vec<&String> = config.config_map().iter().filter(|(_, v)| !v.is_empty()).map(|(k, _)| k).collect();
+		names.sort();
+		for name in names {
+			println!("{} - {}", name, config.mode(name).as_str());
+		}
+		return Ok(());
+	}
+
+	let name = &args[0];
+	manager.list(Some(name))?; // errors if there are no groups of the name
+	if args.len() == 1 {
+		println!("{} is in {} mode", name, manager.config().mode(name).as_str());
+		return Ok(());
+	}
+	let mode = GroupMode::parse(&args[1]).ok_or_else(|| {
+		PmanError::InvalidArgs(format!("Unknown mode '{}', expected auto or manual", args[1]))
+	})?;
+	let activated = manager.set_mode(name, mode)?;
+	println!("{} is in {} mode", name, mode.as_str());
+	if let Some(group) = activated {
+		println!("Swapped to {} - {}", group.priority, group.base_path);
+	}
+	return Ok(());
+}
@@ -0,0 +1,52 @@
+use std::env;
+
+use config::VERSIONS_FILE;
+use error::PmanError;
+use pman::Manager;
+use utils;
+
+pub const DESCRIPTION: &'static str = "Pins the group used for a name in the current directory and below";
+
+pub fn print_usage() {
+	println!("Usage: pman pin <name> <priority> [args]\n\
+			 \0   name       (required) Name of the configuration group\n\
+			 \0   priority   (required unless removing) The group closest to but no less than this priority is used,\n\
+			 \0              eg: cfg.priority>=priority. For the highest priority pass -1. A version requirement\n\
+			 \0              like ^1.8, ~11 or >=17 pins the highest group of a matching version.\n\
+			 \0              Also takes an alias or one of lowest, active and previous, see 'pman help alias'.\n\
+			 \0Writes the pin into the {file} file of the current directory, the nearest pin found walking up\n\
+			 \0from a directory wins. See 'pman current' for what is used where.\n\
+			 \0possible args are:\n\
+			 \0   -exact,-e  Pins exactly the given priority or version instead of the closest\n\
+			 \0   -remove,-r Removes the pin of name from the current directory", file=VERSIONS_FILE);
+}
+
+pub fn run(manager: Manager, args: &[String]) -> Result<(), PmanError> {
+	if args.len() < 2 {
+		print_usage();
+		return Err(PmanError::InvalidArgs(String::from("Expected arguments <name> <priority>")));
+	}
+	let name = &args[0];
+	let mut exact = false;
+	for arg in args[2..].iter() {
+		match arg.to_ascii_lowercase().as_ref() {
+			"-e" | "-exact" => exact = true,
+			_ => return Err(PmanError::InvalidArgs(format!("Unexpected or unknown argument '{}'", arg)))
+		}
+	}
+	let remove = args[1].eq_ignore_ascii_case("-r") || args[1].eq_ignore_ascii_case("-remove");
+
+	let dir = env::current_dir()?;
+	if remove {
+		if manager.unpin(&dir, name)? {
+			println!("Removed the pin of {} from \"{}\"", name, dir.join(VERSIONS_FILE).display());
+		} else {
+			println!("{} is not pinned in \"{}\"", name, dir.join(VERSIONS_FILE).display());
+		}
+		return Ok(());
+	}
+
+	let group = manager.pin(&dir, name, &utils::parse_selector(&args[1], exact)?)?;
+	println!("Pinned {} to {} - {} in \"{}\"", name, group.priority, group.base_path, dir.join(VERSIONS_FILE).display());
+	return Ok(());
+}
@@ -0,0 +1,96 @@
+use error::PmanError;
+use pman::Manager;
+use utils;
+
+pub const DESCRIPTION: &'static str = "Saves and uses profiles, named sets of groups which are swapped to together";
+
+pub fn print_usage() {
+	println!("Usage: pman profile [action] <args>\n\
+			 \0possible actions are:\n\
+			 \0   list\n\
+			 \0              Lists the profiles, * marks profiles whose groups are all active\n\
+			 \0   use <profile>\n\
+			 \0              Swaps to every group of the profile at once, if any of them fails nothing changes\n\
+			 \0   save <profile> [names]\n\
+			 \0              Saves the active groups as the profile, with names only the groups of those names.\n\
+			 \0              An existing profile of the same name is replaced\n\
+			 \0   set <profile> <name> <priority> [-exact]\n\
+			 \0              Makes the profile use the group of name closest to but no less than priority,\n\
+			 \0              or exactly priority with -exact. For the highest priority pass -1. A version requirement\n\
+			 \0              like ^1.8, ~11 or >=17 uses the highest group of a matching version.\n\
+			 \0              Also takes an alias or one of lowest, active and previous, see 'pman help alias'.\n\
+			 \0   remove <profile>\n\
+			 \0              Removes the profile, the active groups dont change");
+}
+
+pub fn run(mut manager: Manager, args: &[String]) -> Result<(), PmanError> {
+	if args.len() == 0 {
+		print_usage();
+		return Err(PmanError::InvalidArgs(String::from("Expected an action, one of list, use, save, set or remove")));
+	}
+	let action = args[0].to_ascii_lowercase();
+	if action != "list" && args.len() < 2 {
+		print_usage();
+		return Err(PmanError::InvalidArgs(format!("Expected the name of the profile to {}", action)));
+	}
+
+	match action.as_ref() {
+		"list" => {
+			let config = manager.config();
+			if config.profiles().is_empty() {
+				println!("No profiles, save one with 'pman profile save <profile>'");
+			}
+			for (profile, selectors) in config.profiles().iter() {
+				let ast = if config.profile_matches(profile) { "*" } else { " " };
+				let groups: Vec<String> = selectors.iter().map(|(name, sel)| format!("{} {}", name, sel.spec())).collect();
+				println!("{} {} - {}", ast, profile, groups.join(", "));
+			}
+		},
+		"use" => {
+			for group in manager.use_profile(&args[1])? {
+				println!("Swapped {} to {} - {}", group.name, group.priority, group.base_path);
+			}
+		},
+		"save" => {
+			let names = if args.len() > 2 { Some(&args[2..]) } else { None };
+			manager.config_mut().save_profile(&args[1], names);
+			let count = manager.config().profile(&args[1]).map_or(0, |p| p.len());
+			manager.save()?;
+			println!("Saved {} active group(s) as profile {}", count, args[1]);
+		},
+		"set" => {
+			if args.len() < 4 {
+				print_usage();
+				return Err(PmanError::InvalidArgs(String::from("Expected arguments <profile> <name> <priority>")));
+			}
+			let mut exact = false;
+			for arg in args[4..].iter() {
+				match arg.to_ascii_lowercase().as_ref() {
+					"-e" | "-exact" => exact = true,
+					_ => return Err(PmanError::InvalidArgs(format!("Unexpected or unknown argument '{}'", arg)))
+				}
+			}
+			let selector = utils::parse_selector(&args[3], exact)?;
+			if manager.config().select(&args[2], &selector).is_none() {
+				println!("No group of {} matches {} yet, the profile cant be used until one does", args[2], selector);
+			}
+			let spec = selector.spec();
+			manager.config_mut().set_profile_group(&args[1], &args[2], selector);
+			manager.save()?;
+			println!("Profile {} uses {} {}", args[1], args[2], spec);
+		},
+		"remove" => {
+			if manager.config_mut().remove_profile(&args[1]) {
+				manager.save()?;
+				println!("Removed profile {}", args[1]);
+			} else {
+				return Err(PmanError::InvalidArgs(format!("No profile named {}", args[1])));
+			}
+		},
+		_ => {
+			print_usage();
+			return Err(PmanError::InvalidArgs(format!("Unknown action \"{}\"", args[0])));
+		}
+	}
+	return Ok(());
+}
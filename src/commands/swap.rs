@@ -1,7 +1,9 @@
-use config::Config;
-use config::LinkGroup;
+use config::{Config, GroupMode, LinkGroup};
+use error::PmanError;
+use pman::Manager;
 use std;
 use std::io::prelude::*;
+use utils;
 
 
 pub const DESCRIPTION: &'static str = "Updates the current installation for a configuration group";
@@ -11,86 +13,59 @@ pub fn print_usage() {
 			 \0   name       (required) Name of configuration group\n\
 			 \0   priority   (optional) If not set you will be prompted to choose what config to activate\n\
 			 \0              however if set then the a config closest to but no less than this param will be selected,\n\
-			 \0              eg: cfg.priority>=priority. For the highest priority pass -1, for the lowest pass 0.");
+			 \0              eg: cfg.priority>=priority. For the highest priority pass -1, for the lowest pass 0.\n\
+			 \0              A version requirement like ^1.8, ~11 or >=17 selects the highest group of a matching version.\n\
+			 \0              Also takes an alias or one of lowest, active and previous, see 'pman help alias'.\n\
+			 \0All links of the group are swapped in at once, if any of them fails the previous group stays active.\n\
+			 \0The name is put in manual mode so the group stays active, see 'pman help mode'.");
 }
 
-pub fn run(mut config: Config, args: &[String]) {
+pub fn run(mut manager: Manager, args: &[String]) -> Result<(), PmanError> {
 	
 	if args.len() == 0 {
-		println!("Expected additional argument [name]");
 		print_usage();
-	} else {
-		
-		let cfg_to_activate: Option<LinkGroup>;
-		if args.len() == 1 {
-			cfg_to_activate = prompt_swap(&config, &args[0]);
-			if cfg_to_activate.is_none() {
-					println!("Invalid selection");
-					std::process::exit(1);
-			}
-		} else {
-			// check for 2nd param, priority
-			let sel_p = args[1].parse::<i64>().unwrap_or_else(|_| {
-					println!("Invalid priority '{}', expected number.", args[1]);
-					std::process::exit(1);
-			});
-			
-			let match_result;
-			if sel_p < 0 {
-				match_result = config.select_highest_group(&args[0]);
-			} else {
-				match_result = config.select_closest_group(&args[0], sel_p as u64);
-			}
-			
-			match match_result {
-				Some(found_cfg) => {
-					cfg_to_activate = Some(found_cfg.clone());
-				},
-				None => {
-					println!("No matching configuration found");
-					std::process::exit(1);
-				}
-			}
-			
+		return Err(PmanError::InvalidArgs(String::from("Expected additional argument [name]")));
+	}
+	let was_auto = manager.config().mode(&args[0]) == GroupMode::Auto;
+	if args.len() == 1 {
+		match prompt_swap(manager.config(), &args[0])? {
+			Some(new_cfg) => {
+				println!("Swapping to {} - {}", new_cfg.priority, new_cfg.base_path);
+				manager.activate(&new_cfg)?;
+			},
+			None => return Ok(())
 		}
+	} else {
+		// check for 2nd param, priority
+		let selector = utils::parse_selector(&args[1], false)?;
 		
-		if cfg_to_activate.is_some() {
-			{ // umm, borrow config... these fucking borrows are going to be the death of me
-				// deactivate our current config if it exists
-				let curr_cfg = config.active_config_entry(&args[0]);
-				if curr_cfg.is_some() { // deactivate the current config
-					curr_cfg.unwrap().deactivate(&config.cmd_dir());
-				}
-			}
-			
-			// activate our new config
-			let new_cfg = cfg_to_activate.unwrap();
-			println!("Swapping to {} - {}", new_cfg.priority, new_cfg.base_path);
-			
-//			set_active_config(&mut config, new_cfg);
-//			let mut m_cfg = &mut config;
-			config.set_active(&new_cfg.name, &new_cfg.priority);
-			new_cfg.activate(&config.cmd_dir());
-			
-			if let Err(e) = config.write() {
-				println!("Failed to persist data. {}", e);
-			}
-		}
+		let new_cfg = manager.swap(&args[0], &selector)?;
+		println!("Swapped to {} - {}", new_cfg.priority, new_cfg.base_path);
 	}
+	if was_auto {
+		println!("{} is in manual mode now, 'pman mode {} auto' goes back to the highest group", args[0], args[0]);
+	}
+	return Ok(());
 }
 
-fn prompt_swap(config: &Config, name: &String) -> Option<LinkGroup> {
-	let cfg_vec:&Vec<LinkGroup> = config.config_entrys_by_name(&name).unwrap_or_else(||{
-		println!("No configs found for '{}'", name);
-		std::process::exit(1);
-	});
+/// prompts for which group to swap to, None if they chose not to swap
+fn prompt_swap(config: &Config, name: &String) -> Result<Option<LinkGroup>, PmanError> {
+	let cfg_vec:&Vec<LinkGroup> = config.config_entrys_by_name(name).ok_or_else(||{
+		PmanError::MissingGroup {
+			name: name.to_owned(),
+			selector: String::from("*")
+		}
+	})?;
 	// prompt swap
 	println!("Choose from the selections below. Asterik(*) is the current active configuration.");
 	for i in 0..cfg_vec.len() {
 		let entry = cfg_vec.get(i).unwrap();
 		let mut ast = " ";
 		if config.is_active(entry) { ast = "*" }
-		println!("{}  {}. {} - {}",ast, (i+1), entry.priority, entry.base_path)
+		match entry.version {
+			Some(ref version) => println!("{}  {}. {} ({}) - {}",ast, (i+1), entry.priority, version, entry.base_path),
+			None => println!("{}  {}. {} - {}",ast, (i+1), entry.priority, entry.base_path)
+		}
 	}
 	
 	print!("Enter number to swap to or press enter to do nothing: ");
@@ -100,19 +75,15 @@ fn prompt_swap(config: &Config, name: &String) -> Option<LinkGroup> {
 	line = String::from(line.trim()); // trim to remove newline chars
 	
 	if line == "" { // no problem, they dont wanna do anything
-		std::process::exit(0);
+		return Ok(None);
 	}
 	
-	// parse their selection
-	let selection_num = line.parse::<usize>().unwrap_or_else(|_| {
-			println!("Invalid selection");
-			std::process::exit(1);
-	});
-	
-	if let Some(c) = cfg_vec.get(selection_num-1) {
-		return Some(c.clone());
+	// parse their selection, shown numbers start at 1
+	let selection = line.parse::<usize>().ok().and_then(|n| n.checked_sub(1)).and_then(|i| cfg_vec.get(i));
+	match selection {
+		Some(c) => return Ok(Some(c.clone())),
+		None => return Err(PmanError::InvalidArgs(String::from("Invalid selection")))
 	}
-	return None;
 }
 
 
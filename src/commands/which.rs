@@ -0,0 +1,50 @@
+use error::PmanError;
+use output::{self, Format, Report};
+use pman::{LinkOwner, Manager, Resolution};
+
+pub const DESCRIPTION: &'static str = "Shows what a command in the command directory really runs";
+
+pub fn print_usage() {
+	println!("Usage: pman which <name>\n\
+			 \0   name       (required) Name of the link, or its file name in the command directory\n\
+			 \0Shows whether the name comes from a standalone link or an active group, the link pman made for it,\n\
+			 \0the file it runs and any executables of the same name earlier on the PATH which run instead.");
+}
+
+pub fn run(manager: Manager, format: Format, args: &[String]) -> Result<(), PmanError> {
+	if args.len() != 1 {
+		print_usage();
+		return Err(PmanError::InvalidArgs(String::from("Expected exactly one argument <name>")));
+	}
+
+	return output::print(format, &manager.which(&args[0])?);
+}
+
+impl Report for Resolution {
+	fn print_text(&self) {
+		match self.owner {
+			LinkOwner::Standalone => println!("{} is a standalone link", self.name),
+			LinkOwner::Group { ref name, priority } => println!("{} is linked by group {}/{}", self.name, name, priority)
+		}
+		println!("   link:   {} ({}){}", self.link_path.display(), self.kind.as_str(), missing(self.link_exists));
+		println!("   target: {}{}", self.target.display(), missing(self.target_exists));
+
+		if !self.on_path {
+			println!("The command directory is not on the PATH, {} cant be run by name", self.name);
+		}
+		for shadow in self.shadowed_by.iter() {
+			if self.on_path {
+				println!("Shadowed by {}, it comes earlier on the PATH and runs instead", shadow.display());
+			} else {
+				println!("Runs {} instead", shadow.display());
+			}
+		}
+	}
+}
+
+fn missing(exists: bool) -> &'static str {
+	if exists {
+		return "";
+	}
+	return " - doesnt exist!";
+}
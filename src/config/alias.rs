@@ -0,0 +1,101 @@
+use config::*;
+use error::PmanError;
+
+/// Aliases are names for one group of a name, eg. lts for java 180. They are kept on the groups so they go with them,
+/// an alias can be used anywhere a priority is taken, see GroupSelector::Alias
+impl Config {
+
+	/// the group of name which has the alias
+	pub fn select_alias_group(&self, name: &String, alias: &str) -> Option<LinkGroup> {
+		let cfg_vec = self.config_entrys_by_name(name)?;
+		return cfg_vec.iter().find(|g| g.aliases.iter().any(|a| a == alias)).cloned();
+	}
+
+	/// Gives the alias to the group of name with priority. An alias is on one group of a name at most, so its taken off
+	/// the group which had it before, its priority is returned. Errors with InvalidArgs if the alias would be read as
+	/// something else, eg. a number or highest, and with MissingGroup if there is no such group
+	pub fn set_alias(&mut self, name: &String, alias: &str, priority: u64) -> Result<Option<u64>, PmanError> {
+		match GroupSelector::parse(alias) {
+			Some(GroupSelector::Alias(_)) => {},
+			_ => return Err(PmanError::InvalidArgs(format!("'{}' cant be an alias, it reads as a priority, version or one of \
+				highest, lowest, active and previous. Start it with a letter and use letters, digits, - and _", alias)))
+		}
+		let cfg_vec = self.config_map.get_mut(name).filter(|v| v.iter().any(|g| g.priority == priority)).ok_or_else(|| {
+			PmanError::MissingGroup {
+				name: name.to_owned(),
+				selector: priority.to_string()
+			}
+		})?;
+		let mut taken_from = None;
+		for group in cfg_vec.iter_mut() {
+			if group.priority == priority {
+				if !group.aliases.iter().any(|a| a == alias) {
+					group.aliases.push(alias.to_owned());
+				}
+			} else if let Some(i) = group.aliases.iter().position(|a| a == alias) {
+				group.aliases.remove(i);
+				taken_from = Some(group.priority);
+			}
+		}
+		return Ok(taken_from);
+	}
+
+	/// takes the alias off the group of name which has it, returns the priority of that group
+	pub fn remove_alias(&mut self, name: &String, alias: &str) -> Option<u64> {
+		let cfg_vec = self.config_map.get_mut(name)?;
+		for group in cfg_vec.iter_mut() {
+			if let Some(i) = group.aliases.iter().position(|a| a == alias) {
+				group.aliases.remove(i);
+				return Some(group.priority);
+			}
+		}
+		return None;
+	}
+
+	/// every alias of the groups of name with the priority of the group its on, sorted by alias
+	pub fn aliases(&self, name: &String) -> Vec<(String, u64)> {
+		let mut aliases = Vec::new();
+		for group in self.config_entrys_by_name(name).into_iter().flatten() {
+			aliases.extend(group.aliases.iter().map(|a| (a.to_owned(), group.priority)));
+		}
+		aliases.sort();
+		return aliases;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use config::*;
+
+	#[test]
+	fn aliases_and_built_in_selectors() {
+		let mut config = Config::new(String::from("cmd"));
+		for pri in [170, 180, 110].iter() {
+			config.insert_config_entry(LinkGroup::new(String::from("java"), *pri)).unwrap();
+		}
+		let java = String::from("java");
+		let select = |config: &Config, spec: &str| config.select(&java, &GroupSelector::parse(spec).unwrap()).map(|g| g.priority);
+
+		assert_eq!(config.set_alias(&java, "lts", 180).unwrap(), None);
+		assert_eq!(select(&config, "lts"), Some(180));
+		assert_eq!(config.set_alias(&java, "lts", 110).unwrap(), Some(180));
+		assert_eq!(select(&config, "lts"), Some(110));
+		config.set_alias(&java, "legacy", 170).unwrap();
+		assert_eq!(config.aliases(&java), vec![(String::from("legacy"), 170), (String::from("lts"), 110)]);
+		for bad in ["170", "=170", "^1.8", "v17", "highest", "Previous", "-lts", "l ts"].iter() {
+			assert!(config.set_alias(&java, bad, 180).is_err(), "{} was accepted", bad);
+		}
+		assert!(config.set_alias(&java, "stable", 190).is_err());
+		assert_eq!(config.remove_alias(&java, "lts"), Some(110));
+		assert_eq!(select(&config, "lts"), None);
+
+		assert_eq!(select(&config, "lowest"), Some(110));
+		assert_eq!(select(&config, "active"), None);
+		config.swap_active(&java, &170);
+		config.swap_active(&java, &170); // swapping to the active group again keeps the previous one
+		assert_eq!(select(&config, "previous"), None);
+		config.swap_active(&java, &180);
+		assert_eq!(select(&config, "active"), Some(180));
+		assert_eq!(select(&config, "previous"), Some(170));
+	}
+}
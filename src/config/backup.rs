@@ -0,0 +1,117 @@
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::path::Path;
+use std::path::PathBuf;
+
+use config::*;
+use error::PmanError;
+
+/// how many of the previous configs are kept next to the config file
+pub const BACKUP_COUNT: usize = 5;
+
+/// Backups are the configs which were replaced by the last writes, kept next to the config file as "{config}.bak.{n}".
+/// Backup 1 is the most recent one.
+impl Config {
+
+	/// path of backup n of the config file, n starts at 1
+	pub fn backup_path(config_path: &Path, n: usize) -> PathBuf {
+		return Config::sibling_path(config_path, &format!(".bak.{}", n));
+	}
+
+	/// the backups which exist for the config file, most recent first
+	pub fn backups(config_path: &Path) -> Vec<PathBuf> {
+		let mut backups = Vec::new();
+		for n in 1..BACKUP_COUNT+1 {
+			let path = Config::backup_path(config_path, n);
+			if path.exists() {
+				backups.push(path);
+			}
+		}
+		return backups;
+	}
+
+	/// copies the current config file into backup 1, the older backups move down one and the oldest is dropped
+	pub fn rotate_backups(config_path: &Path) -> io::Result<()> {
+		for n in (1..BACKUP_COUNT).rev() {
+			let path = Config::backup_path(config_path, n);
+			if path.exists() {
+				fs::rename(&path, Config::backup_path(config_path, n+1))?;
+			}
+		}
+		fs::copy(config_path, Config::backup_path(config_path, 1))?;
+		return Ok(());
+	}
+
+	/// replaces the config file with backup n. The backup has to be a valid config, the replaced config becomes backup 1
+	/// so the restore can be undone by restoring again
+	pub fn restore_backup(config_path: &Path, n: usize) -> Result<(), PmanError> {
+		let backup_path = Config::backup_path(config_path, n);
+		if n == 0 || n > BACKUP_COUNT || !backup_path.exists() {
+			return Err(PmanError::Io(io::Error::new(io::ErrorKind::NotFound,
+				format!("No config backup {} exists, there are {} backup(s)", n, Config::backups(config_path).len()))));
+		}
+
+		let mut contents = String::new();
+		File::open(&backup_path)?.read_to_string(&mut contents)?;
+		Config::from_toml(&contents)?;
+		return Config::write_atomic(config_path, &contents);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs;
+	use std::path::PathBuf;
+
+	use config::*;
+	use test_dir::TestDir;
+
+	fn config_with_dir(cmd_dir: usize) -> Config {
+		return Config::new(format!("cmd{}", cmd_dir));
+	}
+
+	fn read_cmd_dir(path: &PathBuf) -> String {
+		return Config::read_from(path).unwrap().unwrap().cmd_dir_str().to_owned();
+	}
+
+	#[test]
+	fn writes_rotate_backups() {
+		let dir = TestDir::new("backup-rotate");
+		let cfg_path = dir.join(".pman");
+
+		config_with_dir(0).write_to(&cfg_path).unwrap();
+		assert!(Config::backups(&cfg_path).is_empty());
+		config_with_dir(0).write_to(&cfg_path).unwrap();
+		assert!(Config::backups(&cfg_path).is_empty()); // unchanged configs arent backed up
+
+		for i in 1..BACKUP_COUNT+3 {
+			config_with_dir(i).write_to(&cfg_path).unwrap();
+		}
+		let last = BACKUP_COUNT+2;
+		assert_eq!(read_cmd_dir(&cfg_path), format!("cmd{}", last));
+		assert_eq!(Config::backups(&cfg_path).len(), BACKUP_COUNT);
+		assert_eq!(read_cmd_dir(&Config::backup_path(&cfg_path, 1)), format!("cmd{}", last-1));
+		assert_eq!(read_cmd_dir(&Config::backup_path(&cfg_path, BACKUP_COUNT)), format!("cmd{}", last-BACKUP_COUNT));
+		assert!(!Config::sibling_path(&cfg_path, ".tmp").exists());
+	}
+
+	#[test]
+	fn restore_backup() {
+		let dir = TestDir::new("backup-restore");
+		let cfg_path = dir.join(".pman");
+		for i in 0..3 {
+			config_with_dir(i).write_to(&cfg_path).unwrap();
+		}
+
+		Config::restore_backup(&cfg_path, 2).unwrap();
+		assert_eq!(read_cmd_dir(&cfg_path), "cmd0");
+		assert_eq!(read_cmd_dir(&Config::backup_path(&cfg_path, 1)), "cmd2"); // restoring can be undone
+
+		assert!(Config::restore_backup(&cfg_path, BACKUP_COUNT).is_err());
+		fs::write(Config::backup_path(&cfg_path, 1), "not a config").unwrap();
+		assert!(Config::restore_backup(&cfg_path, 1).is_err());
+		assert_eq!(read_cmd_dir(&cfg_path), "cmd0");
+	}
+}
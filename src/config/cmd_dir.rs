@@ -0,0 +1,386 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+
+use toml;
+
+use config::*;
+use config::link_backend::{is_batch_shim, remove_path};
+use error::NotOwned;
+
+/// name of the manifest file in the command directory
+pub const MANIFEST_NAME: &'static str = ".pman-manifest";
+
+/// name of the file in the command directory which holds the path of the config its managed by, dispatch links read it
+/// to find their config
+pub const CONFIG_POINTER: &'static str = ".pman-config";
+
+/// version of the manifest layout, same idea as the config format version
+const MANIFEST_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct ManifestFile {
+	format_version: u32,
+	#[serde(default)]
+	files: BTreeMap<String, ManifestEntry>
+}
+
+impl CmdDir {
+
+	/// opens the command directory at path and reads its manifest. force lets pman replace and remove files it doesnt own
+	pub fn open(path: PathBuf, force: bool) -> io::Result<CmdDir> {
+		let manifest_path = path.join(MANIFEST_NAME);
+		let mut cmd_dir = CmdDir {
+			path: path,
+			manifest: BTreeMap::new(),
+			has_manifest: false,
+			force: force
+		};
+
+		if manifest_path.exists() {
+			let mut contents = String::new();
+			File::open(&manifest_path)?.read_to_string(&mut contents)?;
+			let file: ManifestFile = toml::from_str(&contents).map_err(|e| {
+				io::Error::new(io::ErrorKind::InvalidData, format!("Invalid manifest \"{}\", {}", manifest_path.display(), e))
+			})?;
+			if file.format_version > MANIFEST_VERSION {
+				return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Manifest \"{}\" is newer than this pman understands, update pman", manifest_path.display())));
+			}
+			cmd_dir.manifest = file.files;
+			cmd_dir.has_manifest = true;
+		}
+		return Ok(cmd_dir);
+	}
+
+	pub fn path(&self) -> &PathBuf {
+		return &self.path;
+	}
+
+	/// false until the manifest was saved the first time, see Config::open_cmd_dir
+	pub fn has_manifest(&self) -> bool {
+		return self.has_manifest;
+	}
+
+	pub fn is_forced(&self) -> bool {
+		return self.force;
+	}
+
+	/// every file pman made in the command directory by file name
+	pub fn manifest(&self) -> &BTreeMap<String, ManifestEntry> {
+		return &self.manifest;
+	}
+
+	/// the kind the file of the link named name pointing at source_path was recorded with, None if the manifest has no such file
+	pub fn recorded_kind(&self, name: &str, source_path: &Path) -> Option<LinkKind> {
+		return self.manifest.iter().find(|(file_name, entry)| **file_name == entry.kind.backend().link_name(name, source_path)).map(|(_, entry)| entry.kind);
+	}
+
+	/// checks what is at link_path compared to the manifest
+	pub fn ownership(&self, link_path: &Path) -> io::Result<Ownership> {
+		if fs::symlink_metadata(link_path).is_err() {
+			return Ok(Ownership::Missing);
+		}
+		match self.manifest.get(&CmdDir::_file_name(link_path)) {
+			None => return Ok(Ownership::Unowned),
+			Some(entry) => {
+				if entry.hash == hash_entry(link_path)? {
+					return Ok(Ownership::Owned);
+				}
+				return Ok(Ownership::Modified);
+			}
+		}
+	}
+
+	/// errors with NotOwned if pman may not replace or remove whatever is at link_path. Anything goes when forced
+	pub fn check_owned(&self, link_path: &Path) -> io::Result<()> {
+		if self.force {
+			return Ok(());
+		}
+		match self.ownership(link_path)? {
+			Ownership::Missing | Ownership::Owned => return Ok(()),
+			Ownership::Modified => return Err(NotOwned { path: link_path.to_owned(), modified: true }.into_io()),
+			Ownership::Unowned => return Err(NotOwned { path: link_path.to_owned(), modified: false }.into_io())
+		}
+	}
+
+	/// makes a link of the given kind at link_path and records it in the manifest
+	pub fn create(&mut self, link_path: &Path, source_path: &Path, kind: LinkKind) -> io::Result<()> {
+		self.check_owned(link_path)?;
+		if self.force {
+			// backends may refuse to replace files they didnt make, forcing means replacing them anyway
+			remove_path(link_path)?;
+		}
+		kind.backend().create(link_path, source_path)?;
+		self.record(link_path, kind)?;
+		return self.save();
+	}
+
+	/// removes the link at link_path and drops it from the manifest
+	pub fn remove(&mut self, link_path: &Path, kind: LinkKind) -> io::Result<()> {
+		self.check_owned(link_path)?;
+		if self.force {
+			remove_path(link_path)?;
+		} else {
+			kind.backend().remove(link_path)?;
+		}
+		self.forget(link_path);
+		return self.save();
+	}
+
+	/// records the file at link_path as made by pman with its current contents, the manifest isnt saved
+	pub fn record(&mut self, link_path: &Path, kind: LinkKind) -> io::Result<()> {
+		let entry = ManifestEntry {
+			kind: kind,
+			hash: hash_entry(link_path)?
+		};
+		self.manifest.insert(CmdDir::_file_name(link_path), entry);
+		return Ok(());
+	}
+
+	/// drops link_path from the manifest, the manifest isnt saved
+	pub fn forget(&mut self, link_path: &Path) {
+		self.manifest.remove(&CmdDir::_file_name(link_path));
+	}
+
+	/// replaces every entry of the manifest, the manifest isnt saved
+	pub fn set_manifest(&mut self, manifest: BTreeMap<String, ManifestEntry>) {
+		self.manifest = manifest;
+	}
+
+	/// writes the manifest, the same way as the config through a temp file
+	pub fn save(&mut self) -> io::Result<()> {
+		let file = ManifestFile {
+			format_version: MANIFEST_VERSION,
+			files: self.manifest.clone()
+		};
+		let contents = toml::to_string(&file).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+		let manifest_path = self.path.join(MANIFEST_NAME);
+		let tmp_path = self.path.join(format!("{}.tmp", MANIFEST_NAME));
+		{
+			let mut file = File::create(&tmp_path)?;
+			file.write_all(contents.as_bytes())?;
+			file.sync_all()?;
+		}
+		fs::rename(&tmp_path, &manifest_path)?;
+		self.has_manifest = true;
+		return Ok(());
+	}
+
+	/// the config file the command directory at path is managed by, None if its not known
+	pub fn config_pointer(path: &Path) -> Option<PathBuf> {
+		let mut contents = String::new();
+		File::open(path.join(CONFIG_POINTER)).ok()?.read_to_string(&mut contents).ok()?;
+		let line = contents.trim();
+		if line == "" {
+			return None;
+		}
+		return Some(PathBuf::from(line));
+	}
+
+	/// records cfg_path as the config this command directory is managed by, nothing is written if it already is
+	pub fn set_config_pointer(&self, cfg_path: &Path) -> io::Result<()> {
+		// the dispatcher reads the pointer from wherever its run, so it has to be absolute
+		let cfg_path = &fs::canonicalize(cfg_path).unwrap_or_else(|_| cfg_path.to_owned());
+		if !self.path.is_dir() || CmdDir::config_pointer(&self.path).as_ref() == Some(cfg_path) {
+			return Ok(());
+		}
+		let tmp_path = self.path.join(format!("{}.tmp", CONFIG_POINTER));
+		fs::write(&tmp_path, format!("{}\n", cfg_path.display()))?;
+		return fs::rename(&tmp_path, self.path.join(CONFIG_POINTER));
+	}
+
+	/**
+	Records the file at link_path as made by pman if its exactly the link pman would make there. Used for command directories
+	from before the manifest existed. The expected link is made in a scratch directory and compared, batch shims in the
+	older single line format are taken as well. Returns true if the file was adopted
+	*/
+	pub fn adopt(&mut self, link_path: &Path, source_path: &Path, kind: LinkKind) -> io::Result<bool> {
+		if self.ownership(link_path)? != Ownership::Unowned {
+			return Ok(false);
+		}
+
+		let scratch_dir = self.path.join(format!(".pman-adopt-{}", process::id()));
+		fs::create_dir_all(&scratch_dir)?;
+		let expected_path = scratch_dir.join(link_path.file_name().unwrap());
+		let matches = kind.backend().create(&expected_path, source_path).and_then(|_| {
+			return Ok(hash_entry(&expected_path)? == hash_entry(link_path)?);
+		});
+		let _ = fs::remove_dir_all(&scratch_dir);
+
+		let mut adopt = matches.unwrap_or(false);
+		if !adopt && kind == LinkKind::Batch {
+			let mut contents = Vec::new();
+			File::open(link_path)?.read_to_end(&mut contents)?;
+			adopt = is_batch_shim(&String::from_utf8_lossy(&contents));
+		}
+		if adopt {
+			self.record(link_path, kind)?;
+		}
+		return Ok(adopt);
+	}
+
+	fn _file_name(link_path: &Path) -> String {
+		return link_path.file_name().map_or(String::new(), |n| n.to_string_lossy().into_owned());
+	}
+}
+
+impl Config {
+
+	/// opens the command directory with its manifest. For command directories from before the manifest the links which are
+	/// exactly what the config would make are adopted and everything else is left unowned. The adopted links are only kept
+	/// in memory, the manifest is written with the first change made through the command directory. Only the scratch
+	/// directory adopt compares in is made and removed again
+	pub fn open_cmd_dir(&self, force: bool) -> io::Result<CmdDir> {
+		let mut cmd_dir = CmdDir::open(self.cmd_dir(), force)?;
+		if cmd_dir.has_manifest() || !cmd_dir.path().exists() {
+			return Ok(cmd_dir);
+		}
+
+		let dir = cmd_dir.path().clone();
+		for link in self.links.iter() {
+			let (link_path, source_path, kind) = link.rel_link(&dir, &dir, None);
+			if fs::symlink_metadata(&link_path).is_ok() {
+				cmd_dir.adopt(&link_path, &source_path, kind)?;
+			}
+		}
+		for cfg_vec in self.config_map.values() {
+			for group in cfg_vec.iter() {
+				let source_dir = PathBuf::from(&group.base_path);
+				for link in group.links.iter() {
+					let (link_path, source_path, kind) = link.rel_link(&dir, &source_dir, group.kind);
+					if fs::symlink_metadata(&link_path).is_ok() {
+						cmd_dir.adopt(&link_path, &source_path, kind)?;
+					}
+				}
+			}
+		}
+		return Ok(cmd_dir);
+	}
+}
+
+/// fnv-1a, its small and stable between builds which the std hashers arent
+fn fnv1a(bytes: &[u8], mut hash: u64) -> u64 {
+	for b in bytes {
+		hash ^= *b as u64;
+		hash = hash.wrapping_mul(0x100000001b3);
+	}
+	return hash;
+}
+
+/// hash of the contents of the file at path, for a symlink its the hash of where it points
+pub fn hash_entry(path: &Path) -> io::Result<String> {
+	let offset_basis = 0xcbf29ce484222325;
+	let hash;
+	if fs::symlink_metadata(path)?.file_type().is_symlink() {
+		let target = fs::read_link(path)?;
+		hash = fnv1a(target.to_string_lossy().as_bytes(), fnv1a(b"symlink:", offset_basis));
+	} else {
+		let mut file = File::open(path)?;
+		let mut buf = [0; 8192];
+		let mut h = offset_basis;
+		loop {
+			let n = file.read(&mut buf)?;
+			if n == 0 { break; }
+			h = fnv1a(&buf[..n], h);
+		}
+		hash = h;
+	}
+	return Ok(format!("{:016x}", hash));
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs;
+
+	use config::*;
+	use error::PmanError;
+	use test_dir::TestDir;
+
+	fn scratch_dir(name: &str) -> TestDir {
+		let dir = TestDir::new(&format!("cmd-dir-{}", name));
+		fs::create_dir_all(dir.join("cmd")).unwrap();
+		fs::write(dir.join("tool"), "").unwrap();
+		return dir;
+	}
+
+	#[test]
+	fn refuses_files_pman_didnt_make() {
+		let dir = scratch_dir("owned");
+		let link_path = dir.join("cmd").join("tool");
+		let source_path = dir.join("tool");
+		let mut cmd_dir = CmdDir::open(dir.join("cmd"), false).unwrap();
+
+		fs::write(&link_path, "users own script").unwrap();
+		assert_eq!(cmd_dir.ownership(&link_path).unwrap(), Ownership::Unowned);
+		let err = cmd_dir.create(&link_path, &source_path, LinkKind::Shell).unwrap_err();
+		match PmanError::from(err) {
+			PmanError::LinkConflict { ref name, .. } => assert_eq!(name, "tool"),
+			e => panic!("expected a link conflict, got {:?}", e)
+		}
+		assert!(cmd_dir.remove(&link_path, LinkKind::Shell).is_err());
+		assert_eq!(fs::read_to_string(&link_path).unwrap(), "users own script");
+
+		// forcing replaces it, after that pman owns it
+		let mut forced = CmdDir::open(dir.join("cmd"), true).unwrap();
+		forced.create(&link_path, &source_path, LinkKind::Shell).unwrap();
+		let mut cmd_dir = CmdDir::open(dir.join("cmd"), false).unwrap();
+		assert_eq!(cmd_dir.ownership(&link_path).unwrap(), Ownership::Owned);
+
+		// changed by hand since
+		fs::write(&link_path, "edited").unwrap();
+		assert_eq!(cmd_dir.ownership(&link_path).unwrap(), Ownership::Modified);
+		assert!(cmd_dir.remove(&link_path, LinkKind::Shell).is_err());
+
+		cmd_dir.create(&dir.join("cmd").join("other"), &source_path, LinkKind::Shell).unwrap();
+		cmd_dir.remove(&dir.join("cmd").join("other"), LinkKind::Shell).unwrap();
+		assert!(!cmd_dir.manifest().contains_key("other"));
+	}
+
+	#[test]
+	fn removes_links_as_the_kind_they_were_made() {
+		let dir = scratch_dir("kind");
+		let cmd = dir.join("cmd");
+		fs::create_dir_all(dir.join("jdk")).unwrap();
+		let home = Link::new(String::from("java_home"), String::from("jdk"));
+		let mut cmd_dir = CmdDir::open(cmd.clone(), false).unwrap();
+		home.create_link(&mut cmd_dir, &dir, Some(LinkKind::Batch)).unwrap();
+		assert_eq!(cmd_dir.recorded_kind("java_home", &dir.join("jdk")), Some(LinkKind::Symlink));
+
+		// without the directory the link would resolve to a batch shim, java_home.bat
+		fs::remove_dir_all(dir.join("jdk")).unwrap();
+		home.remove_link(&mut cmd_dir, &dir, Some(LinkKind::Batch)).unwrap();
+		assert!(fs::symlink_metadata(cmd.join("java_home")).is_err());
+		assert!(cmd_dir.manifest().is_empty());
+	}
+
+	#[test]
+	fn adopts_links_from_before_the_manifest() {
+		let dir = scratch_dir("adopt");
+		let cmd = dir.join("cmd");
+		let mut config = Config::new(cmd.to_str().unwrap().to_owned());
+		let mut tool = Link::new(String::from("tool"), dir.join("tool").to_str().unwrap().to_owned());
+		tool.kind = Some(LinkKind::Shell);
+		config.add_link(tool.clone());
+		let mut mine = tool.clone();
+		mine.name = String::from("mine");
+		config.add_link(mine);
+
+		// an old pman made tool, the user made mine
+		LinkKind::Shell.backend().create(&cmd.join("tool"), &dir.join("tool")).unwrap();
+		fs::write(cmd.join("mine"), "users own script").unwrap();
+
+		let mut cmd_dir = config.open_cmd_dir(false).unwrap();
+		assert_eq!(fs::read_dir(&cmd).unwrap().count(), 2); // opening it changes nothing
+		assert_eq!(cmd_dir.ownership(&cmd.join("tool")).unwrap(), Ownership::Owned);
+		assert_eq!(cmd_dir.ownership(&cmd.join("mine")).unwrap(), Ownership::Unowned);
+
+		cmd_dir.save().unwrap();
+		assert_eq!(config.open_cmd_dir(false).unwrap().ownership(&cmd.join("tool")).unwrap(), Ownership::Owned);
+	}
+}
@@ -1,27 +1,30 @@
 
 use std::env;
-use std;
 use std::fs;
 use std::fs::File;
 use std::path::PathBuf;
 use std::path::Path;
-use std::io::BufReader;
 use std::io;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 
 use std::io::prelude::*;
 
 use config::*;
-
+use error::PmanError;
 
 impl Config {
 	
 	pub fn new(cmd_dir: String) -> Config {
 		return Config {
 				active_configs: HashMap::new(),
+				previous_configs: HashMap::new(),
+				modes: HashMap::new(),
 				config_map: HashMap::new(),
 				links: Vec::new(),
-				cmd_dir_str: cmd_dir.to_owned()
+				profiles: BTreeMap::new(),
+				cmd_dir_str: cmd_dir.to_owned(),
+				cmd_dir_override: None
 		};
 	}
 	
@@ -34,8 +37,17 @@ impl Config {
 		self.cmd_dir_str = val;
 	}
 	
+	/// uses dir as the command directory instead of the configured one, the override isnt saved with the config.
+	/// None goes back to the configured directory
+	pub fn set_cmd_dir_override(&mut self, dir: Option<PathBuf>) {
+		self.cmd_dir_override = dir;
+	}
+	
 	/// the command directory as absolute path. 
 	pub fn cmd_dir(&self) -> PathBuf {
+		if let Some(ref dir) = self.cmd_dir_override {
+			return dir.clone();
+		}
 		let mut cmd_dir = PathBuf::from(&self.cmd_dir_str);
 		
 		// check if it needs to be relative
@@ -48,7 +60,17 @@ impl Config {
 		return cmd_dir;
 	}
 	
-	pub fn insert_config_entry(&mut self, entry: LinkGroup) -> bool {
+	/// makes the command directory if it doesnt exist
+	pub fn ensure_cmd_dir(&self) -> io::Result<()> {
+		let cmd_dir = self.cmd_dir();
+		if !cmd_dir.exists() {
+			fs::create_dir_all(&cmd_dir)?;
+		}
+		return Ok(());
+	}
+	
+	/// adds the group, errors if the name already has a group at the same priority
+	pub fn insert_config_entry(&mut self, entry: LinkGroup) -> Result<(), PmanError> {
 		let name = entry.name.to_owned();
 		if !self.config_map.contains_key(&name) {
 			self.config_map.insert(name.clone(), Vec::new());
@@ -58,12 +80,16 @@ impl Config {
 		// check for duplicate priority
 		for e in &m_vec {
 			if e.priority == entry.priority {
-				return false;
+				return Err(PmanError::DuplicatePriority {
+					name: name,
+					priority: entry.priority
+				});
 			}
 		}
 		m_vec.push(entry);
+		m_vec.sort_by(|a, b| b.cmp_rank(a)); // highest first, same as when read
 		self.config_map.insert(name.to_owned(), m_vec);
-		return true;
+		return Ok(());
 	}
 	
 	/// removes a config entry by name and priority
@@ -107,14 +133,11 @@ impl Config {
 	
 	/// retrives the current LinkGroup for the given name
 	pub fn active_config_entry(&self, name: &String) -> Option<&LinkGroup> {
-		let cfg_vec = self.config_map.get(name);
-		if cfg_vec.is_none() {
-			return None;
-		}
-		
-		for cfg_entry in cfg_vec.unwrap().iter() {
-			if self.is_active(cfg_entry) {
-				return Some(cfg_entry);
+		if let Some(cfg_vec) = self.config_map.get(name) {
+			for cfg_entry in cfg_vec.iter() {
+				if self.is_active(cfg_entry) {
+					return Some(cfg_entry);
+				}
 			}
 		}
 		
@@ -132,7 +155,7 @@ impl Config {
 	
 	
 	pub fn select_group(&self, name: &String, pri: u64) -> Option<LinkGroup> {
-		if let Some(cfg_vec) = self.config_entrys_by_name(&name) {
+		if let Some(cfg_vec) = self.config_entrys_by_name(name) {
 			
 			for i in 0..cfg_vec.len() {
 				if cfg_vec[i].priority == pri {
@@ -146,13 +169,13 @@ impl Config {
 	}
 	
 	
-	/// selects the group with the highest priority
+	/// selects the highest group, by version if the groups have one, see LinkGroup::cmp_rank
 	pub fn select_highest_group(&self, name: &String) -> Option<LinkGroup> {
-		if let Some(cfg_vec) = self.config_entrys_by_name(&name) {
+		if let Some(cfg_vec) = self.config_entrys_by_name(name) {
 			let mut sel_grp: Option<&LinkGroup> = None; // matched group
 			
 			for i in 0..cfg_vec.len() {
-				if sel_grp.is_none() || cfg_vec[i].priority > sel_grp.unwrap().priority {
+				if sel_grp.is_none() || cfg_vec[i].cmp_rank(sel_grp.unwrap()) == ::std::cmp::Ordering::Greater {
 					sel_grp = cfg_vec.get(i);
 				}
 			}
@@ -162,14 +185,21 @@ impl Config {
 		return None;
 	}
 	
-	/// selects the best configuration available for the given name and priority where ret.priority>=pri.
+	/// selects the lowest group, the opposite of select_highest_group
+	pub fn select_lowest_group(&self, name: &String) -> Option<LinkGroup> {
+		let cfg_vec = self.config_entrys_by_name(name)?;
+		return cfg_vec.iter().min_by(|a, b| a.cmp_rank(b)).cloned();
+	}
+	
+	/// selects the best configuration available for the given name and priority where ret.priority>=pri. Of those the
+	/// lowest is picked, by version if the groups have one like select_highest_group, see LinkGroup::cmp_rank
 	pub fn select_closest_group(&self, name: &String, pri: u64) -> Option<LinkGroup> {
-		if let Some(cfg_vec) = self.config_entrys_by_name(&name) {
+		if let Some(cfg_vec) = self.config_entrys_by_name(name) {
 			let mut sel_grp: Option<&LinkGroup> = None; // matched group
 			
 			for i in 0..cfg_vec.len() {
-				// gotta be greater than the pri param and have a smaller priority than our current chosen group
-				if cfg_vec[i].priority>=pri && (sel_grp.is_none() || cfg_vec[i].priority<=sel_grp.unwrap().priority) {
+				// gotta be greater than the pri param and rank lower than our current chosen group
+				if cfg_vec[i].priority>=pri && (sel_grp.is_none() || cfg_vec[i].cmp_rank(sel_grp.unwrap()) == ::std::cmp::Ordering::Less) {
 					sel_grp = cfg_vec.get(i);
 				}
 			}
@@ -180,10 +210,58 @@ impl Config {
 		
 	}
 	
+	/// selects the highest group whose version meets the requirement, groups without a version are skipped
+	pub fn select_version_group(&self, name: &String, req: &VersionReq) -> Option<LinkGroup> {
+		let cfg_vec = self.config_entrys_by_name(name)?;
+		return cfg_vec.iter()
+			.filter(|g| g.parsed_version().is_some_and(|v| req.matches(&v)))
+			.max_by(|a, b| a.cmp_rank(b))
+			.cloned();
+	}
+	
+	/// selects a group of the given name with the selector
+	pub fn select(&self, name: &String, selector: &GroupSelector) -> Option<LinkGroup> {
+		match *selector {
+			GroupSelector::Highest => return self.select_highest_group(name),
+			GroupSelector::Lowest => return self.select_lowest_group(name),
+			GroupSelector::Active => return self.active_config_entry(name).cloned(),
+			GroupSelector::Previous => return self.previous(name).and_then(|pri| self.select_group(name, pri)),
+			GroupSelector::Alias(ref alias) => return self.select_alias_group(name, alias),
+			GroupSelector::Closest(pri) => return self.select_closest_group(name, pri),
+			GroupSelector::Exact(pri) => return self.select_group(name, pri),
+			GroupSelector::Version(ref req) => return self.select_version_group(name, req)
+		}
+	}
 	
 	/// changes the current active configuration
 	pub fn set_active(&mut self, name: &String, priority: &u64) {
-		self.active_configs.insert(name.clone(), priority.clone());
+		self.active_configs.insert(name.clone(), *priority);
+	}
+	
+	/// makes no group of the name active
+	pub fn clear_active(&mut self, name: &String) {
+		self.active_configs.remove(name);
+	}
+	
+	/// changes the active group of the name like set_active and remembers the group active before as the previous one,
+	/// unless its the same group
+	pub fn swap_active(&mut self, name: &String, priority: &u64) {
+		if let Some(prev) = self.active_configs.insert(name.clone(), *priority).filter(|prev| prev != priority) {
+			self.previous_configs.insert(name.clone(), prev);
+		}
+	}
+	
+	/// priority of the group which was active before the last swap of the name
+	pub fn previous(&self, name: &String) -> Option<u64> {
+		return self.previous_configs.get(name).cloned();
+	}
+	
+	/// sets or clears the priority which counts as previous for the name
+	pub fn set_previous(&mut self, name: &String, priority: Option<u64>) {
+		match priority {
+			Some(priority) => self.previous_configs.insert(name.clone(), priority),
+			None => self.previous_configs.remove(name)
+		};
 	}
 	
 	pub fn add_link(&mut self, link: Link) {
@@ -201,276 +279,137 @@ impl Config {
 	pub fn remove_link(&mut self, name: &String) -> bool {
 		for i in 0..self.links.len() {
 			if name.eq(&self.links[i].name) {
-				&self.links.remove(i);
+				self.links.remove(i);
 				return true;
 			}
 		}
 		return false;
 	}
 	
+	/// finds a group which has a link with the given name
+	pub fn group_with_link(&self, name: &String) -> Option<&LinkGroup> {
+		for cfg_vec in self.config_map.values() {
+			for cfg_entry in cfg_vec {
+				if cfg_entry.get_link(name).is_some() {
+					return Some(cfg_entry);
+				}
+			}
+		}
+		
+		return None;
+	}
+	
 	pub fn get_link(&self, name: &String) -> Option<&Link> {
 		for link in &self.links {
 			if link.name.eq(name) {
-				return Some(&link);
+				return Some(link);
 			}
 		}
 		
 		return None;
 	}
 	
-	pub fn cfg_path() -> Result<PathBuf, io::Error> {
-		let mut config_path = env::current_exe().unwrap();
-		let mut config_name: String = String::from(".");
-		// i think i need to keep this as a OsString, but i cant fucking figure out how to prepend a "." to it... so fuck it, convert it to UTF-8 string
-		config_name.push_str(config_path.file_stem().unwrap().to_str().unwrap()); 
-		
-		config_path.pop(); // remove the executable name
-		config_path.push(Path::new(&config_name)); // add in config name which is ".{exe_name}"
-		
-		return Ok(config_path);
+	/// writes the config to the config file found by cfg_path
+	pub fn write(&self) -> Result<(), PmanError> {
+		return self.write_to(&Config::cfg_path()?);
 	}
 	
-	pub fn write(&self) -> io::Result<()> {
-		
-		let config_path = try!(Config::cfg_path());
-		
-		let mut file = try!(File::create(config_path));
-		try!(file.write_all(self.cmd_dir_str.as_bytes()));
-		try!(file.write_all(b"\n"));// new line
-		
-		// active configs
-		for (name, path) in &self.active_configs {
-			try!(file.write_all(format!("{}\n{}\n", name, path).as_bytes()));
-		}
-		try!(file.write_all(b"\n"));// new line
-		
-		// standalone links 
-		for link in &self.links {
-			try!(file.write_all(format!("{}\n{}\n", link.name, link.path).as_bytes()));
-		}
-		try!(file.write_all(b"\n"));// new line
-		
-		// configs
-		for (name, cfg_entries) in &self.config_map {
-			try!(file.write_all(format!("{}\n", name).as_bytes()));
-			for cfg_entry in cfg_entries {
-				try!(file.write_all(format!("{}\n{}\n", cfg_entry.base_path, cfg_entry.priority).as_bytes()));
-				for link in &cfg_entry.links {
-					try!(file.write_all(format!("{}\n{}\n", link.name, link.path).as_bytes()));
-				}
-				try!(file.write_all(b"\n"));// new line
-			}
-			try!(file.write_all(b"\n\n"));// 2 more newline between entries
-		}
-		
-		try!(file.sync_all());
-		return Ok(());
+	/// writes the config to the given config file. The file is replaced atomically and the config it replaces is kept as a backup,
+	/// see write_atomic
+	pub fn write_to(&self, config_path: &Path) -> Result<(), PmanError> {
+		let contents = self.to_toml()?;
+		return Config::write_atomic(config_path, &contents);
 	}
 	
 	/**
-	Reads the config file and returns a config object
+	Replaces the config file with contents so it is either the old or the new config, never half written. The contents go to a temp
+	file next to it which is synced and then renamed over the config. The replaced config is rotated into the backups first,
+	nothing is written when the contents havent changed.
 	*/
-	pub fn read() -> Result<Option<Config>, io::Error> {
-		
-		let config_path = try!(Config::cfg_path());
-		
-		let mut active_configs = HashMap::new();
-		let mut links = Vec::new();
-		let mut configs = HashMap::new(); // config objects which will be returned
-		let cmd_dir_str;
+	pub fn write_atomic(config_path: &Path, contents: &str) -> Result<(), PmanError> {
+		// per user configs live in a pman directory which may not exist yet
+		if let Some(parent) = config_path.parent() {
+			fs::create_dir_all(parent)?;
+		}
 		
 		if config_path.exists() {
-			let file = try!(File::open(config_path));
-			let mut reader = BufReader::new(file);
-			let mut line: String = String::new();
-			if try!(reader.read_line(&mut line)) <= 0 {
-				println!("Unexpected EOF while reading config");
-				std::process::exit(1);
+			let mut old_contents = String::new();
+			File::open(config_path)?.read_to_string(&mut old_contents)?;
+			if old_contents == contents {
+				return Ok(());
 			}
-			cmd_dir_str = String::from(line.trim());
-			
-			try!(Config::_read_active_configs(&mut active_configs, &mut reader));
-			try!(Config::_read_links(&mut links, &mut reader));
-			try!(Config::_read_configs(&mut configs, &mut reader));
-		} else {
-			return Ok(None);
+			Config::rotate_backups(config_path)?;
 		}
 		
-		let config = Config {
-				active_configs: active_configs,
-				config_map: configs,
-				links: links,
-				cmd_dir_str: cmd_dir_str
-		};
-		
-		
-		// make the commands directory if it doesnt exist
-		let cmd_dir = config.cmd_dir();
-		if !cmd_dir.exists() {
-			try!(fs::create_dir_all(&cmd_dir));
+		let tmp_path = Config::sibling_path(config_path, ".tmp");
+		{
+			let mut file = File::create(&tmp_path)?;
+			file.write_all(contents.as_bytes())?;
+			file.sync_all()?;
 		}
-		
-		
-		return Ok(Some(config));
-	}
-	
-	
-
-	fn _read_active_configs(map: &mut HashMap<String, u64>, reader: &mut BufReader<File>) -> io::Result<()> {
-		let mut name = String::new();
-		
-		if let Err(e) = Config::_read_to_empty_line(reader, &mut |line| {
-			if name == "" {
-				name = line.clone();
-			} else {
-				// name is set, then we have path
-				
-				let priority = line.parse::<u64>().unwrap_or_else(|e| {
-					println!("Invalid priority \"{}\" while parsing configuration \"{}\".\n\"{}\"", line, name, e);
-					std::process::exit(1);
-				});
-				map.insert(name.clone(), priority);
-				name.clear();
+		fs::rename(&tmp_path, config_path)?;
+		
+		// the rename lives in the directory, sync it too so the rename survives a crash
+		#[cfg(unix)]
+		{
+			if let Some(parent) = config_path.parent() {
+				let dir = if parent.as_os_str().is_empty() { Path::new(".") } else { parent };
+				File::open(dir)?.sync_all()?;
 			}
-			return Ok(());
-		}) {
-			// error reading config
-			println!("Error occured while reading active configuration section. {}", e);
-			std::process::exit(1);
 		}
-		
 		return Ok(());
 	}
 	
-	fn _read_links(links: &mut Vec<Link>, reader: &mut BufReader<File>) -> io::Result<()> {
-		
-		let mut name = String::new();
-		
-		if let Err(e) = Config::_read_to_empty_line(reader, &mut |line| {
-				if name == "" {
-					name = line.clone();
-				} else {
-					// name is set, then we have path
-					links.push(Link {
-						name: name.clone(), 
-						path: line.clone()
-					});
-					name.clear();
-				}
-				return Ok(());
-			}) {
-			// error reading config
-			println!("Error occured while reading active links section. {}", e);
-			std::process::exit(1);
-		}
-		
-		return Ok(());
+	/// path next to the config file with suffix appended to its name, eg. ".pman" and ".tmp" gives ".pman.tmp"
+	pub(crate) fn sibling_path(config_path: &Path, suffix: &str) -> PathBuf {
+		let mut path = config_path.to_owned().into_os_string();
+		path.push(suffix);
+		return PathBuf::from(path);
 	}
 	
-	/// reads all configuration entries from the reader, expected to be at the line before the next entries
-	fn _read_configs(configs: &mut HashMap<String, Vec<LinkGroup>>, reader: &mut BufReader<File>) -> io::Result<()> {
-		
-		let mut name = String::new();
-		while try!(reader.read_line(&mut name)) > 0 {
-			name = String::from(name.trim()); // trim out newlines
-			
-			let mut cfg_vec = Vec::new();
-			while let Some(c) = try!(Config::_read_config(name.clone(), reader)) {
-		//		println!("Add config {}", c.base_path);
-				cfg_vec.push(c);
-			}
-			// sort by priority, we want highest first
-			cfg_vec.sort_by(|a, b| b.priority.cmp(&a.priority));
-			configs.insert(name.clone(), cfg_vec);
-			
-			name.clear();
-		}
-		
-		return Ok(());
+	/// reads the config file found by cfg_path, see read_from
+	pub fn read() -> Result<Option<Config>, PmanError> {
+		return Config::read_from(&Config::cfg_path()?);
 	}
 	
-	
-	/// Reads the config from a buffered reader
-	fn _read_config(name: String, reader: &mut BufReader<File>) -> Result<Option<LinkGroup>, io::Error> {
-		let mut line = String::new();
-	
-		let mut base_path = String::new();
-		let mut priority: Option<u64> = None;
-		let mut links = Vec::new();
-		let mut link_name = String::new();
-		
-
-		// TODO: rewrite this garbage to not use line numbers. We always know the first 2 lines, then use a loop on the rest
-		// keeping this error thing here cause im gonna need it to throw if the first 2 lines arent there, maybe, I need to return some error but IO seems wrong
-//		io::Error::new(io::ErrorKind::Other, "");
+	/**
+	Reads the given config file and returns a config object, None when there is no config file. Configs saved in the legacy
+	line based format are read too but nothing is written, see migrate for rewriting them as toml.
+	The command directory isnt created here, see ensure_cmd_dir.
+	*/
+	pub fn read_from(config_path: &Path) -> Result<Option<Config>, PmanError> {
 		
-		let mut line_num = 0;
-		while try!(reader.read_line(&mut line)) > 0 {
-			line = String::from(line.trim()); // trim to remove newline chars
-			
-			
-	//		println!("\"{}\"", line);
-			if line_num == 0 {
-				base_path = line.clone();
-			} else {
-				// at this point new line signals end of config
-				if line == "" { break; }
-				if line_num == 1 {
-					priority = Some(line.parse::<u64>().unwrap_or_else(|e| {
-							println!("Invalid priority \"{}\" while parsing configuration \"{}\".\n\"{}\"", line, name, e);
-							std::process::exit(1);
-					}));
-				} else {
-					
-					if line_num%2 == 0 {
-						// is link name declaration
-						link_name = line.clone();
-					} else {
-						links.push(Link {
-							name: link_name.clone(),
-							path: line.clone()
-						});
-					}
-					
-				}
-			}
-			
-		    line.clear();
-		    line_num+=1;
+		if !config_path.exists() {
+			return Ok(None);
 		}
 		
-		if priority.is_none() {
-			return Ok(None);
-		} else {
-			return Ok(Some(LinkGroup {
-				name: name,
-				base_path: base_path,
-				priority: priority.unwrap(),
-				links: links
-			}));
+		let mut contents = String::new();
+		File::open(config_path)?.read_to_string(&mut contents)?;
+		
+		if Config::is_toml(&contents) {
+			return Ok(Some(Config::from_toml(&contents)?));
 		}
+		return Ok(Some(Config::from_legacy(contents.as_bytes())?));
 	}
 	
-	/// reads line from reader until a blank line is read, where it will stop parsing.
-	/// used in our configuration
-	fn _read_to_empty_line<F>(reader: &mut BufReader<File>, acc_fn: &mut F) -> io::Result<()>
-		where F: FnMut(&String)-> io::Result<()> {
-			
-		let mut line = String::new();
-		while try!(reader.read_line(&mut line)) > 0 {
-			line = String::from(line.trim()); // trim to remove newline chars
-			
-			// new line signals end of config
-			if line == "" {
-				break;
-			}
-			
-			try!(acc_fn(&line));
-			
-			line.clear();
+	/// rewrites a config saved in the legacy line based format as toml, the old file is kept next to it with a .legacy extension.
+	/// Returns the path of the kept file, None when there was nothing to migrate. Nothing is printed, thats up to the caller
+	pub fn migrate(config_path: &Path) -> Result<Option<PathBuf>, PmanError> {
+		if !config_path.exists() {
+			return Ok(None);
 		}
 		
-		return Ok(());
+		let mut contents = String::new();
+		File::open(config_path)?.read_to_string(&mut contents)?;
+		if Config::is_toml(&contents) {
+			return Ok(None);
+		}
+		let config = Config::from_legacy(contents.as_bytes())?;
+		
+		let legacy_path = Config::sibling_path(config_path, ".legacy");
+		fs::copy(config_path, &legacy_path)?;
+		config.write_to(config_path)?;
+		return Ok(Some(legacy_path));
 	}
 	
-}
\ No newline at end of file
+}
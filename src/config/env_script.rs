@@ -0,0 +1,306 @@
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+use config::*;
+
+/// name of the env scripts in the command directory without their extension, eg. .pman-env.sh
+pub const ENV_SCRIPT: &'static str = ".pman-env";
+
+impl Shell {
+
+	pub fn all() -> [Shell; 4] {
+		return [Shell::Sh, Shell::Fish, Shell::PowerShell, Shell::Cmd];
+	}
+
+	/// name of the shell as its accepted on the command line
+	pub fn as_str(&self) -> &'static str {
+		match *self {
+			Shell::Sh => return "sh",
+			Shell::Fish => return "fish",
+			Shell::PowerShell => return "powershell",
+			Shell::Cmd => return "cmd"
+		}
+	}
+
+	/// parses a shell name, the common sh compatible shells and pwsh are taken as well
+	pub fn parse(name: &str) -> Option<Shell> {
+		match name.to_ascii_lowercase().as_ref() {
+			"sh" | "bash" | "zsh" | "dash" | "ksh" => return Some(Shell::Sh),
+			"fish" => return Some(Shell::Fish),
+			"powershell" | "pwsh" | "ps1" => return Some(Shell::PowerShell),
+			"cmd" | "bat" => return Some(Shell::Cmd),
+			_ => return None
+		}
+	}
+
+	/// extension of the scripts for this shell
+	pub fn extension(&self) -> &'static str {
+		match *self {
+			Shell::Sh => return "sh",
+			Shell::Fish => return "fish",
+			Shell::PowerShell => return "ps1",
+			Shell::Cmd => return "cmd"
+		}
+	}
+
+	/// guesses the shell pman was started from. On windows cmd.exe sets PROMPT and PowerShell doesnt, elsewhere the file
+	/// name of $SHELL is used. Falls back to PowerShell on windows and sh everywhere else
+	pub fn detect() -> Shell {
+		return Shell::_detect(&|var| env::var_os(var));
+	}
+
+	fn _detect(env_var: &dyn Fn(&str) -> Option<OsString>) -> Shell {
+		if cfg!(windows) {
+			return if env_var("PROMPT").is_some() { Shell::Cmd } else { Shell::PowerShell };
+		}
+		let shell = env_var("SHELL").map(PathBuf::from);
+		let name = shell.as_ref().and_then(|s| s.file_name()).map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+		return Shell::parse(&name).unwrap_or(Shell::Sh);
+	}
+
+	/// val quoted so this shell takes it literally. cmd has no such quotes, only % is escaped as it is in batch files
+	pub fn quote(&self, val: &str) -> String {
+		match *self {
+			Shell::Sh => return format!("'{}'", val.replace('\'', "'\\''")),
+			Shell::Fish => return format!("'{}'", val.replace('\\', "\\\\").replace('\'', "\\'")),
+			Shell::PowerShell => return format!("'{}'", val.replace('\'', "''")),
+			Shell::Cmd => return val.replace('%', "%%")
+		}
+	}
+
+	/// the line setting name to val in this shell, val is quoted so its taken literally
+	pub fn set_var(&self, name: &str, val: &str) -> String {
+		match *self {
+			Shell::Sh => return format!("export {}={}", name, self.quote(val)),
+			Shell::Fish => return format!("set -gx {} {}", name, self.quote(val)),
+			Shell::PowerShell => return format!("$env:{} = {}", name, self.quote(val)),
+			Shell::Cmd => return format!("@set \"{}={}\"", name, self.quote(val))
+		}
+	}
+
+	/// the line removing the var name in this shell
+	pub fn unset_var(&self, name: &str) -> String {
+		match *self {
+			Shell::Sh => return format!("unset {}", name),
+			Shell::Fish => return format!("set -e {}", name),
+			Shell::PowerShell => return format!("Remove-Item Env:{} -ErrorAction SilentlyContinue", name),
+			Shell::Cmd => return format!("@set \"{}=\"", name)
+		}
+	}
+
+	/// the line setting or with None removing the var name
+	pub fn apply_var(&self, name: &str, val: Option<&String>) -> String {
+		match val {
+			Some(val) => return self.set_var(name, val),
+			None => return self.unset_var(name)
+		}
+	}
+
+	/// a script for this shell which sets vars and removes the ones without value, with a comment saying where it came from
+	pub fn render(&self, vars: &[(String, Option<String>)]) -> String {
+		let (comment, newline) = match *self {
+			Shell::Cmd => ("@rem", "\r\n"),
+			_ => ("#", "\n")
+		};
+		let mut script = format!("{} env vars of the active pman groups, rewritten whenever a group is activated{}", comment, newline);
+		for (name, val) in vars.iter() {
+			script.push_str(&self.apply_var(name, val.as_ref()));
+			script.push_str(newline);
+		}
+		return script;
+	}
+
+	/**
+	The snippet for the rc file of this shell which sets it up for pman. cmd_dir is put first on the PATH unless its already
+	on it and the env script of the shell is sourced. pman becomes a wrapper function which runs pman_exe, then makes the shell
+	forget the commands it looked up and sources the env script again, so a swap takes effect in the shell straight away.
+	cmd has no functions, there pman is a doskey macro
+	*/
+	pub fn init_script(&self, cmd_dir: &Path, pman_exe: &Path) -> String {
+		let dir = cmd_dir.to_string_lossy();
+		let exe = pman_exe.to_string_lossy();
+		let script = _script_path(cmd_dir, *self);
+		let script = script.to_string_lossy();
+		match *self {
+			Shell::Sh => return format!("\
+# pman shell integration\n\
+case \":$PATH:\" in\n\
+\t*:{dir}:*) ;;\n\
+\t*) export PATH={dir}:\"$PATH\" ;;\n\
+esac\n\
+[ -f {script} ] && . {script}\n\
+pman() {{\n\
+\t{exe} \"$@\"\n\
+\tset -- $?\n\
+\thash -r 2>/dev/null\n\
+\t[ -f {script} ] && . {script}\n\
+\treturn $1\n\
+}}\n",
+				dir=self.quote(&dir), exe=self.quote(&exe), script=self.quote(&script)),
+			Shell::Fish => return format!("\
+# pman shell integration\n\
+if not contains -- {dir} $PATH\n\
+\tset -gx PATH {dir} $PATH\n\
+end\n\
+test -f {script}; and source {script}\n\
+function pman\n\
+\t{exe} $argv\n\
+\tset -l pman_status $status\n\
+\ttest -f {script}; and source {script}\n\
+\treturn $pman_status\n\
+end\n",
+				dir=self.quote(&dir), exe=self.quote(&exe), script=self.quote(&script)),
+			Shell::PowerShell => return format!("\
+# pman shell integration\n\
+if (($env:PATH -split [IO.Path]::PathSeparator) -notcontains {dir}) {{\n\
+\t$env:PATH = {dir} + [IO.Path]::PathSeparator + $env:PATH\n\
+}}\n\
+if (Test-Path {script}) {{ . {script} }}\n\
+function pman {{\n\
+\t& {exe} @args\n\
+\t$pmanStatus = $LASTEXITCODE\n\
+\tif (Test-Path {script}) {{ . {script} }}\n\
+\t$global:LASTEXITCODE = $pmanStatus\n\
+}}\n",
+				dir=self.quote(&dir), exe=self.quote(&exe), script=self.quote(&script)),
+			Shell::Cmd => return format!("\
+@rem pman shell integration\r\n\
+@echo ;%PATH%; | find /i \";{dir};\" >nul || set \"PATH={dir};%PATH%\"\r\n\
+@if exist \"{script}\" call \"{script}\"\r\n\
+@doskey pman=\"{exe}\" $* $T if exist \"{script}\" call \"{script}\"\r\n",
+				dir=self.quote(&dir), exe=self.quote(&exe), script=self.quote(&script))
+		}
+	}
+}
+
+impl CmdDir {
+
+	/// the env script for shell in this command directory
+	pub fn env_script_path(&self, shell: Shell) -> PathBuf {
+		return _script_path(&self.path, shell);
+	}
+
+	/// writes the env script of every shell with vars, scripts are written even without vars so sourcing them never fails.
+	/// Nothing is written if the command directory doesnt exist
+	pub fn write_env_scripts(&self, vars: &[(String, Option<String>)]) -> io::Result<()> {
+		if !self.path.is_dir() {
+			return Ok(());
+		}
+		for shell in Shell::all().iter() {
+			let path = _script_path(&self.path, *shell);
+			let contents = shell.render(vars);
+			if fs::read_to_string(&path).ok().as_ref() == Some(&contents) {
+				continue;
+			}
+			let tmp_path = self.path.join(format!("{}.{}.tmp", ENV_SCRIPT, shell.extension()));
+			fs::write(&tmp_path, contents)?;
+			fs::rename(&tmp_path, &path)?;
+		}
+		return Ok(());
+	}
+}
+
+impl Config {
+
+	/// the env vars of every active group, see env_for. Names are taken in order, so when two set the same var the later
+	/// name wins
+	pub fn active_env(&self) -> Vec<(String, Option<String>)> {
+		let mut names: Vec<&String> = self.config_map().keys().collect();
+		names.sort();
+		let mut vars = Vec::new();
+		for name in names {
+			vars.extend(self.env_for(name, self.active_config_entry(name)));
+		}
+		// a var another name sets isnt removed
+		let set: Vec<String> = vars.iter().filter(|(_, val)| val.is_some()).map(|(var, _)| var.to_owned()).collect();
+		vars.retain(|(var, val)| val.is_some() || !set.contains(var));
+		return vars;
+	}
+
+	/// the env vars of group, which is one of the groups named name or None if none is used. Vars only other groups of
+	/// the name set are None, they have to be removed so they dont stay behind from the group used before
+	pub fn env_for(&self, name: &String, group: Option<&LinkGroup>) -> Vec<(String, Option<String>)> {
+		let mut vars: Vec<(String, Option<String>)> = Vec::new();
+		for other in self.config_entrys_by_name(name).into_iter().flatten() {
+			for var in other.env.keys() {
+				if group.is_none_or(|g| !g.env.contains_key(var)) && !vars.iter().any(|(n, _)| n == var) {
+					vars.push((var.to_owned(), None));
+				}
+			}
+		}
+		if let Some(group) = group {
+			vars.extend(group.env_vars().into_iter().map(|(var, val)| (var, Some(val))));
+		}
+		vars.sort();
+		return vars;
+	}
+}
+
+fn _script_path(dir: &Path, shell: Shell) -> PathBuf {
+	return dir.join(format!("{}.{}", ENV_SCRIPT, shell.extension()));
+}
+
+#[cfg(test)]
+mod tests {
+	use std::ffi::OsString;
+	use std::fs;
+	use std::path::Path;
+	use std::process::Command;
+
+	use config::*;
+	use test_dir::TestDir;
+
+	#[test]
+	fn scripts_quote_values() {
+		let vars = vec![(String::from("JAVA_HOME"), Some(String::from("/opt/it's 100%"))), (String::from("JDK"), None)];
+		assert_eq!(Shell::Sh.render(&vars).lines().nth(1), Some("export JAVA_HOME='/opt/it'\\''s 100%'"));
+		assert_eq!(Shell::Fish.render(&vars).lines().nth(1), Some("set -gx JAVA_HOME '/opt/it\\'s 100%'"));
+		assert_eq!(Shell::PowerShell.render(&vars).lines().nth(1), Some("$env:JAVA_HOME = '/opt/it''s 100%'"));
+		assert_eq!(Shell::Cmd.render(&vars).lines().nth(1), Some("@set \"JAVA_HOME=/opt/it's 100%%\""));
+		assert_eq!(Shell::Sh.render(&vars).lines().nth(2), Some("unset JDK"));
+
+		let mut group = LinkGroup::new(String::from("java"), 180);
+		group.set_base_path(String::from("/opt/jdk8/bin"));
+		group.env.insert(String::from("JAVA_HOME"), String::from("${base}/.."));
+		assert_eq!(group.env_vars(), vec![(String::from("JAVA_HOME"), String::from("/opt/jdk8/bin/.."))]);
+
+		// vars of other groups with the same name are removed
+		let mut config = Config::new(String::from("cmd"));
+		let mut jdk7 = LinkGroup::new(String::from("java"), 170);
+		jdk7.env.insert(String::from("JDK"), String::from("7"));
+		config.insert_config_entry(jdk7.clone()).unwrap();
+		config.insert_config_entry(group.clone()).unwrap();
+		config.set_active(&String::from("java"), &180);
+		assert_eq!(config.active_env(), vec![(String::from("JAVA_HOME"), Some(String::from("/opt/jdk8/bin/.."))), (String::from("JDK"), None)]);
+		assert_eq!(config.env_for(&String::from("java"), Some(&jdk7)), vec![(String::from("JAVA_HOME"), None), (String::from("JDK"), Some(String::from("7")))]);
+
+		if !cfg!(windows) {
+			let fish = |var: &str| if var == "SHELL" { Some(OsString::from("/usr/bin/fish")) } else { None };
+			assert_eq!(Shell::_detect(&fish), Shell::Fish);
+			assert_eq!(Shell::_detect(&|_| None), Shell::Sh);
+		}
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn sh_init_adds_the_cmd_dir_once() {
+		let dir = TestDir::new("init");
+		let cmd_dir = dir.join("my cmd");
+		fs::create_dir_all(&cmd_dir).unwrap();
+		fs::write(cmd_dir.join(".pman-env.sh"), Shell::Sh.render(&[(String::from("PMAN_TEST"), Some(String::from("set")))])).unwrap();
+		let init = dir.join("init.sh");
+		fs::write(&init, Shell::Sh.init_script(&cmd_dir, Path::new("false"))).unwrap();
+
+		let script = format!(". '{0}'; . '{0}'; echo \"$PATH\"; echo $PMAN_TEST; pman; echo $?", init.display());
+		let output = Command::new("sh").arg("-c").arg(script).output().unwrap();
+		let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+		let lines: Vec<&str> = stdout.lines().collect();
+		assert_eq!(lines[0].matches(cmd_dir.to_str().unwrap()).count(), 1);
+		assert!(lines[0].starts_with(cmd_dir.to_str().unwrap()));
+		assert_eq!(&lines[1..], &["set", "1"]); // the wrapper passes the exit code on
+	}
+}
@@ -0,0 +1,259 @@
+use std::io;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+use toml;
+
+use config::*;
+use error::PmanError;
+
+/// version of the config format written by this build, bump it whenever the layout changes in a way older builds cant read
+pub const FORMAT_VERSION: u32 = 1;
+
+/// layout of the config file. Its kept apart from Config so the file can be sorted and stable
+/// while Config keeps its lookup maps
+#[derive(Serialize, Deserialize)]
+struct ConfigFile {
+	format_version: u32,
+	cmd_dir: String,
+	#[serde(default)]
+	active: BTreeMap<String, u64>, // group name -> active priority
+	#[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+	previous: BTreeMap<String, u64>, // group name -> priority active before the last swap
+	#[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+	modes: BTreeMap<String, GroupMode>, // group name -> mode, names without one are in manual mode
+	#[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+	profiles: BTreeMap<String, BTreeMap<String, String>>, // profile name -> group name -> selector spec, eg. "=170"
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	links: Vec<Link>,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	groups: Vec<LinkGroup>
+}
+
+impl Config {
+
+	/// serializes the config into the toml config format
+	pub fn to_toml(&self) -> Result<String, PmanError> {
+		let mut names: Vec<&String> = self.config_map.keys().collect();
+		names.sort();
+
+		let mut groups = Vec::new();
+		for name in names {
+			groups.extend(self.config_map[name].iter().cloned());
+		}
+
+		let file = ConfigFile {
+			format_version: FORMAT_VERSION,
+			cmd_dir: self.cmd_dir_str.clone(),
+			active: self.active_configs.iter().map(|(k, v)| (k.clone(), *v)).collect(),
+			previous: self.previous_configs.iter().map(|(k, v)| (k.clone(), *v)).collect(),
+			// the mode of a name without groups goes with it, a name which is added again starts over
+			modes: self.modes.iter().filter(|(k, _)| self.config_map.get(*k).is_some_and(|v| !v.is_empty()))
+				.map(|(k, v)| (k.clone(), *v)).collect(),
+			profiles: self.profiles.iter().map(|(profile, selectors)| {
+				return (profile.clone(), selectors.iter().map(|(name, sel)| (name.clone(), sel.spec())).collect());
+			}).collect(),
+			links: self.links.clone(),
+			groups: groups
+		};
+
+		return toml::to_string(&file).map_err(|e| PmanError::Io(io::Error::new(io::ErrorKind::InvalidData, e)));
+	}
+
+	/// parses a config from the toml config format
+	pub fn from_toml(contents: &str) -> Result<Config, PmanError> {
+		let file: ConfigFile = toml::from_str(contents).map_err(|e| {
+			PmanError::ConfigParse {
+				line: e.line_col().map_or(0, |(line, _)| line + 1),
+				message: e.to_string()
+			}
+		})?;
+		if file.format_version > FORMAT_VERSION {
+			return Err(PmanError::ConfigVersion(file.format_version));
+		}
+
+		let mut config_map: HashMap<String, Vec<LinkGroup>> = HashMap::new();
+		for group in file.groups {
+			if let Some(version) = group.version.as_ref().filter(|v| Version::parse(v).is_none()) {
+				return Err(PmanError::ConfigParse {
+					line: 0,
+					message: format!("group {} {} has version \"{}\", expected eg. 1.8.0, 17 or 1.7.0_79", group.name, group.priority, version)
+				});
+			}
+			config_map.entry(group.name.clone()).or_default().push(group);
+		}
+		// keep the highest group first, same as the groups were always ordered
+		for cfg_vec in config_map.values_mut() {
+			cfg_vec.sort_by(|a, b| b.cmp_rank(a));
+		}
+
+		let mut profiles = BTreeMap::new();
+		for (profile, specs) in file.profiles {
+			let mut selectors = BTreeMap::new();
+			for (name, spec) in specs {
+				let selector = GroupSelector::parse(&spec).ok_or_else(|| {
+					PmanError::ConfigParse {
+						line: 0,
+						message: format!("profile {} selects \"{}\" for {}, expected eg. 170, =170, ^1.8, highest or an alias", profile, spec, name)
+					}
+				})?;
+				selectors.insert(name, selector);
+			}
+			profiles.insert(profile, selectors);
+		}
+
+		let mut config = Config::new(file.cmd_dir);
+		config.active_configs = file.active.into_iter().collect();
+		config.previous_configs = file.previous.into_iter().collect();
+		config.modes = file.modes.into_iter().collect();
+		config.profiles = profiles;
+		config.links = file.links;
+		config.config_map = config_map;
+		return Ok(config);
+	}
+
+	/// checks if the contents of a config file are in the toml format rather than the legacy line based one
+	pub fn is_toml(contents: &str) -> bool {
+		return contents.lines().any(|l| l.trim_start().starts_with("format_version"));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs;
+
+	use config::*;
+	use error::PmanError;
+	use test_dir::TestDir;
+
+	fn sample_config() -> Config {
+		let mut config = Config::new(String::from("cmd"));
+
+		let mut java7 = LinkGroup::new(String::from("java"), 170);
+		java7.set_base_path(String::from(r"D:\lib\java\jdk1.7.0_79\bin"));
+		java7.add_link(Link::new(String::from("java"), String::from("java.exe")));
+		java7.add_link(Link::new(String::from("java_home"), String::from("../")));
+		let mut java8 = LinkGroup::new(String::from("java"), 180);
+		java8.set_base_path(String::from(r"D:\lib\java\jdk1.8.0_20\bin"));
+		java8.kind = Some(LinkKind::Hardlink);
+		java8.version = Some(String::from("1.8.0_20"));
+		java8.aliases.push(String::from("lts"));
+		let mut javac = Link::new(String::from("javac"), String::from("javac.exe"));
+		javac.kind = Some(LinkKind::PowerShell);
+		java8.add_link(javac);
+		config.insert_config_entry(java7).unwrap();
+		config.insert_config_entry(java8).unwrap();
+		config.set_active(&String::from("java"), &180);
+		config.swap_active(&String::from("java"), &170);
+		config.set_mode(&String::from("java"), GroupMode::Auto);
+		config.save_profile(&String::from("legacy"), None);
+		config.set_profile_group(&String::from("latest"), &String::from("java"), GroupSelector::Highest);
+
+		// names and paths which the legacy format couldnt hold
+		let mut odd = Link::new(String::from("two\nlines"), String::from("C:\\with \"quotes\"\n"));
+		odd.kind = Some(LinkKind::Batch);
+		config.add_link(odd);
+		return config;
+	}
+
+	fn assert_same(a: &Config, b: &Config) {
+		assert_eq!(a.cmd_dir_str(), b.cmd_dir_str());
+		assert_eq!(a.active_configs(), b.active_configs());
+		assert_eq!(a.links(), b.links());
+		assert_eq!(a.config_map(), b.config_map());
+		assert_eq!(a.profiles(), b.profiles());
+		assert_eq!(a.previous(&String::from("java")), b.previous(&String::from("java")));
+		assert_eq!(a.mode(&String::from("java")), b.mode(&String::from("java")));
+	}
+
+	#[test]
+	fn toml_round_trip() {
+		let config = sample_config();
+		let toml = config.to_toml().unwrap();
+		assert!(Config::is_toml(&toml));
+
+		let read = Config::from_toml(&toml).unwrap();
+		assert_same(&config, &read);
+		assert_eq!(read.config_entrys_by_name(&String::from("java")).unwrap()[0].priority, 180); // highest first
+		assert_eq!(read.to_toml().unwrap(), toml);
+	}
+
+	#[test]
+	fn toml_empty_sections() {
+		let mut config = Config::new(String::from("cmd"));
+		config.set_active(&String::from("java"), &170);
+		config.insert_config_entry(LinkGroup::new(String::from("java"), 170)).unwrap();
+
+		let read = Config::from_toml(&config.to_toml().unwrap()).unwrap();
+		assert_same(&config, &read);
+	}
+
+	#[test]
+	fn toml_rejects_newer_format() {
+		match Config::from_toml("format_version = 99\ncmd_dir = \"cmd\"\n") {
+			Err(PmanError::ConfigVersion(99)) => {},
+			_ => panic!("expected a format version error")
+		}
+		assert!(Config::from_toml("format_version = 1\ncmd_dir = \"cmd\"\n").is_ok());
+	}
+
+	#[test]
+	fn parse_errors_have_line_numbers() {
+		match Config::from_toml("format_version = 1\ncmd_dir = \"cmd\"\n[active]\njava = \"abc\"\n") {
+			Err(PmanError::ConfigParse { line, .. }) => assert_eq!(line, 4),
+			_ => panic!("expected a parse error")
+		}
+		match Config::from_legacy("cmd\njava\nabc\n\n".as_bytes()) {
+			Err(PmanError::ConfigParse { line, .. }) => assert_eq!(line, 3),
+			_ => panic!("expected a parse error")
+		}
+	}
+
+	#[test]
+	fn legacy_migration() {
+		let legacy = "cmd\n\
+					  java\n170\n\
+					  \n\
+					  pscp\nD:\\lib\\putty\\pscp.exe\n\
+					  \n\
+					  java\n\
+					  D:\\lib\\java\\jdk1.7.0_79\\bin\n170\njava\njava.exe\njava_home\n../\n\
+					  \n\
+					  D:\\lib\\java\\jdk1.8.0_20\\bin\n180\tcopy\njavac\tps1\njavac.exe\n\
+					  \n\n\n";
+		assert!(!Config::is_toml(legacy));
+		let config = Config::from_legacy(legacy.as_bytes()).unwrap();
+
+		assert_eq!(config.cmd_dir_str(), "cmd");
+		assert_eq!(config.active_configs().get("java"), Some(&170));
+		assert_eq!(config.links().len(), 1);
+		assert_eq!(config.links()[0].path, r"D:\lib\putty\pscp.exe");
+
+		let groups = config.config_entrys_by_name(&String::from("java")).unwrap();
+		assert_eq!(groups.len(), 2);
+		assert_eq!(groups[0].priority, 180);
+		assert_eq!(groups[0].kind, Some(LinkKind::Copy));
+		assert_eq!(groups[0].links[0].kind, Some(LinkKind::PowerShell));
+		assert_eq!(groups[1].links.len(), 2);
+		assert_eq!(groups[1].links[1].path, "../");
+
+		// migrating writes it back as toml
+		let read = Config::from_toml(&config.to_toml().unwrap()).unwrap();
+		assert_same(&config, &read);
+	}
+
+	#[test]
+	fn only_migrate_writes_legacy_configs() {
+		let dir = TestDir::new("migrate");
+		let cfg_path = dir.join(".pman");
+		fs::write(&cfg_path, "cmd\njava\n170\n\n\n\n").unwrap();
+
+		assert_eq!(Config::read_from(&cfg_path).unwrap().unwrap().cmd_dir_str(), "cmd");
+		assert!(!Config::is_toml(&fs::read_to_string(&cfg_path).unwrap()));
+
+		let legacy_path = Config::migrate(&cfg_path).unwrap().unwrap();
+		assert_eq!(fs::read_to_string(&legacy_path).unwrap(), "cmd\njava\n170\n\n\n\n");
+		assert!(Config::is_toml(&fs::read_to_string(&cfg_path).unwrap()));
+		assert_eq!(Config::migrate(&cfg_path).unwrap(), None);
+	}
+}
@@ -0,0 +1,218 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+use std::io::prelude::*;
+
+use config::*;
+use error::PmanError;
+
+/// wraps the reader to count lines, so parse errors can say where they happened
+struct LineReader<R: BufRead> {
+	reader: R,
+	line_num: usize
+}
+
+impl<R: BufRead> LineReader<R> {
+	
+	/// reads the next line into line with the newline chars trimmed off. Returns false once the input is used up
+	fn next_line(&mut self, line: &mut String) -> Result<bool, PmanError> {
+		line.clear();
+		if self.reader.read_line(line)? == 0 {
+			return Ok(false);
+		}
+		self.line_num += 1;
+		let trimmed = String::from(line.trim());
+		*line = trimmed;
+		return Ok(true);
+	}
+	
+	/// parse error for the line which was read last
+	fn error(&self, message: String) -> PmanError {
+		return PmanError::ConfigParse {
+			line: self.line_num,
+			message: message
+		};
+	}
+}
+
+/// Reader for the line based format configs were saved in before the toml format. Its only used to migrate
+/// old configs, they are read once and written back out as toml.
+impl Config {
+	
+	/// Reads a config saved in the legacy line based format. The format is positional, sections are split by blank lines:
+	/// cmd_dir, then active groups (name/priority pairs), then standalone links (name/path pairs) and finally
+	/// each group name followed by its entries (base_path, priority, then name/path pairs of links).
+	pub fn from_legacy<R: BufRead>(reader: R) -> Result<Config, PmanError> {
+		let mut reader = LineReader {
+			reader: reader,
+			line_num: 0
+		};
+		let mut active_configs = HashMap::new();
+		let mut links = Vec::new();
+		let mut configs = HashMap::new();
+		
+		let mut line: String = String::new();
+		if !reader.next_line(&mut line)? {
+			return Err(reader.error(String::from("unexpected end of file, expected the command directory")));
+		}
+		let cmd_dir_str = line;
+		
+		Config::_read_active_configs(&mut active_configs, &mut reader)?;
+		Config::_read_links(&mut links, &mut reader)?;
+		Config::_read_configs(&mut configs, &mut reader)?;
+		
+		let mut config = Config::new(cmd_dir_str);
+		config.active_configs = active_configs;
+		config.config_map = configs;
+		config.links = links;
+		return Ok(config);
+	}
+	
+	fn _read_active_configs<R: BufRead>(map: &mut HashMap<String, u64>, reader: &mut LineReader<R>) -> Result<(), PmanError> {
+		let mut name = String::new();
+		
+		return Config::_read_to_empty_line(reader, &mut |line, reader| {
+			if name == "" {
+				name = line.clone();
+			} else {
+				// name is set, then we have path
+				let priority = line.parse::<u64>().map_err(|e| {
+					reader.error(format!("invalid priority \"{}\" for active group \"{}\", {}", line, name, e))
+				})?;
+				map.insert(name.clone(), priority);
+				name.clear();
+			}
+			return Ok(());
+		});
+	}
+	
+	fn _read_links<R: BufRead>(links: &mut Vec<Link>, reader: &mut LineReader<R>) -> Result<(), PmanError> {
+		
+		let mut name = String::new();
+		
+		return Config::_read_to_empty_line(reader, &mut |line, reader| {
+				if name == "" {
+					name = line.clone();
+				} else {
+					// name is set, then we have path
+					let (link_name, kind) = Config::_split_kind(&name, reader)?;
+					links.push(Link {
+						name: link_name,
+						path: line.clone(),
+						kind: kind
+					});
+					name.clear();
+				}
+				return Ok(());
+			});
+	}
+	
+	/// reads all configuration entries from the reader, expected to be at the line before the next entries
+	fn _read_configs<R: BufRead>(configs: &mut HashMap<String, Vec<LinkGroup>>, reader: &mut LineReader<R>) -> Result<(), PmanError> {
+		
+		let mut name = String::new();
+		while reader.next_line(&mut name)? {
+			let mut cfg_vec = Vec::new();
+			while let Some(c) = Config::_read_config(name.clone(), reader)? {
+				cfg_vec.push(c);
+			}
+			// sort by priority, we want highest first
+			cfg_vec.sort_by_key(|c| ::std::cmp::Reverse(c.priority));
+			configs.insert(name.clone(), cfg_vec);
+		}
+		
+		return Ok(());
+	}
+	
+	
+	/// Reads the config from a buffered reader
+	fn _read_config<R: BufRead>(name: String, reader: &mut LineReader<R>) -> Result<Option<LinkGroup>, PmanError> {
+		let mut line = String::new();
+	
+		let mut base_path = String::new();
+		let mut priority: Option<u64> = None;
+		let mut kind: Option<LinkKind> = None;
+		let mut links = Vec::new();
+		let mut link_name = String::new();
+		
+		// the first 2 lines are always the base path and priority, after that its name/path pairs of links
+		let mut line_num = 0;
+		while reader.next_line(&mut line)? {
+			if line_num == 0 {
+				base_path = line.clone();
+			} else {
+				// at this point new line signals end of config
+				if line == "" { break; }
+				if line_num == 1 {
+					let (priority_str, group_kind) = Config::_split_kind(&line, reader)?;
+					kind = group_kind;
+					priority = Some(priority_str.parse::<u64>().map_err(|e| {
+						reader.error(format!("invalid priority \"{}\" for group \"{}\", {}", priority_str, name, e))
+					})?);
+				} else {
+					
+					if line_num%2 == 0 {
+						// is link name declaration
+						link_name = line.clone();
+					} else {
+						let (link_name, link_kind) = Config::_split_kind(&link_name, reader)?;
+						links.push(Link {
+							name: link_name,
+							path: line.clone(),
+							kind: link_kind
+						});
+					}
+					
+				}
+			}
+			
+			line_num+=1;
+		}
+		
+		match priority {
+			None => return Ok(None),
+			Some(priority) => return Ok(Some(LinkGroup {
+				name: name,
+				base_path: base_path,
+				priority: priority,
+				version: None,
+				aliases: Vec::new(),
+				links: links,
+				kind: kind,
+				env: BTreeMap::new()
+			}))
+		}
+	}
+	
+	/// link kinds were stored after a tab on the line they belong to, eg "java\tsh". Splits such a line into its value and kind
+	fn _split_kind<R: BufRead>(line: &str, reader: &LineReader<R>) -> Result<(String, Option<LinkKind>), PmanError> {
+		match line.find('\t') {
+			None => return Ok((line.to_owned(), None)),
+			Some(i) => {
+				let kind_str = line[i+1..].trim();
+				match LinkKind::parse(kind_str) {
+					Some(kind) => return Ok((line[..i].to_owned(), Some(kind))),
+					None => return Err(reader.error(format!("invalid link kind \"{}\"", kind_str)))
+				}
+			}
+		}
+	}
+	
+	/// reads line from reader until a blank line is read, where it will stop parsing.
+	/// used in our configuration
+	fn _read_to_empty_line<R: BufRead, F>(reader: &mut LineReader<R>, acc_fn: &mut F) -> Result<(), PmanError>
+		where F: FnMut(&String, &LineReader<R>) -> Result<(), PmanError> {
+			
+		let mut line = String::new();
+		while reader.next_line(&mut line)? {
+			// new line signals end of config
+			if line == "" {
+				break;
+			}
+			
+			acc_fn(&line, reader)?;
+		}
+		
+		return Ok(());
+	}
+}
@@ -0,0 +1,404 @@
+use std::env;
+use std::fs;
+use std::fs::File;
+use std::path::Path;
+use std::path::PathBuf;
+use std::io;
+#[cfg(windows)]
+use std::os::windows::fs::{symlink_dir, symlink_file, FileTypeExt};
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+
+use std::io::prelude::*;
+
+use config::*;
+
+/// marker line written into every batch shim, used to tell pmans shims apart from the users own files
+pub const BATCH_SHIM_MARKER: &'static str = "rem pman-shim";
+
+/// env var pointing at the dispatcher executable dispatch links are made of, by default its pman-shim next to pman
+pub const DISPATCHER_ENV: &'static str = "PMAN_DISPATCHER";
+
+/// plain symbolic link, works for files and directories. On windows file symlinks may need elevated rights
+struct SymlinkBackend;
+/// hard link to the file, must be on the same volume as the target
+struct HardlinkBackend;
+/// copy of the file, wont follow along with updates to the target
+struct CopyBackend;
+/// .bat file which runs the target through cmd.exe
+struct BatchBackend;
+/// sh script which execs the target
+struct ShellBackend;
+/// .ps1 script which runs the target through powershell
+struct PowerShellBackend;
+/// copy or hard link of the pman-shim dispatcher, which picks the group to run when its run instead of when its made
+struct DispatchBackend;
+
+impl LinkKind {
+
+	/// every kind of link, in the order help text lists them
+	pub fn all() -> Vec<LinkKind> {
+		return vec![LinkKind::Symlink, LinkKind::Hardlink, LinkKind::Copy, LinkKind::Batch, LinkKind::Shell, LinkKind::PowerShell, LinkKind::Dispatch];
+	}
+
+	/// the kind used for files when neither the link or its group picked one
+	#[cfg(windows)]
+	pub fn platform_default() -> LinkKind {
+		return LinkKind::Batch;
+	}
+
+	/// the kind used for files when neither the link or its group picked one
+	#[cfg(not(windows))]
+	pub fn platform_default() -> LinkKind {
+		return LinkKind::Shell;
+	}
+
+	/// name of the kind as its written in the config and accepted on the command line
+	pub fn as_str(&self) -> &'static str {
+		match *self {
+			LinkKind::Symlink => return "symlink",
+			LinkKind::Hardlink => return "hardlink",
+			LinkKind::Copy => return "copy",
+			LinkKind::Batch => return "bat",
+			LinkKind::Shell => return "sh",
+			LinkKind::PowerShell => return "ps1",
+			LinkKind::Dispatch => return "dispatch"
+		}
+	}
+
+	/// parses a kind from its name, case insensitive. Returns None for unknown names
+	pub fn parse(val: &str) -> Option<LinkKind> {
+		match val.to_ascii_lowercase().as_ref() {
+			"symlink" => return Some(LinkKind::Symlink),
+			"hardlink" => return Some(LinkKind::Hardlink),
+			"copy" => return Some(LinkKind::Copy),
+			"bat" | "batch" => return Some(LinkKind::Batch),
+			"sh" | "shell" => return Some(LinkKind::Shell),
+			"ps1" | "powershell" => return Some(LinkKind::PowerShell),
+			"dispatch" => return Some(LinkKind::Dispatch),
+			_ => return None
+		}
+	}
+
+	/// the backend which makes links of this kind
+	pub fn backend(&self) -> &'static dyn LinkBackend {
+		match *self {
+			LinkKind::Symlink => return &SymlinkBackend,
+			LinkKind::Hardlink => return &HardlinkBackend,
+			LinkKind::Copy => return &CopyBackend,
+			LinkKind::Batch => return &BatchBackend,
+			LinkKind::Shell => return &ShellBackend,
+			LinkKind::PowerShell => return &PowerShellBackend,
+			LinkKind::Dispatch => return &DispatchBackend
+		}
+	}
+}
+
+impl LinkBackend for SymlinkBackend {
+	fn link_name(&self, name: &str, source_path: &Path) -> String {
+		return with_source_ext(name, source_path);
+	}
+
+	fn create(&self, link_path: &Path, source_path: &Path) -> io::Result<()> {
+		remove_path(link_path)?;
+		return symlink(source_path, link_path);
+	}
+
+	fn remove(&self, link_path: &Path) -> io::Result<()> {
+		return remove_path(link_path);
+	}
+}
+
+impl LinkBackend for HardlinkBackend {
+	fn link_name(&self, name: &str, source_path: &Path) -> String {
+		return with_source_ext(name, source_path);
+	}
+
+	fn create(&self, link_path: &Path, source_path: &Path) -> io::Result<()> {
+		require_file(LinkKind::Hardlink, source_path)?;
+		remove_path(link_path)?;
+		return fs::hard_link(source_path, link_path);
+	}
+
+	fn remove(&self, link_path: &Path) -> io::Result<()> {
+		return remove_path(link_path);
+	}
+}
+
+impl LinkBackend for CopyBackend {
+	fn link_name(&self, name: &str, source_path: &Path) -> String {
+		return with_source_ext(name, source_path);
+	}
+
+	fn create(&self, link_path: &Path, source_path: &Path) -> io::Result<()> {
+		require_file(LinkKind::Copy, source_path)?;
+		remove_path(link_path)?;
+		fs::copy(source_path, link_path)?;
+		return Ok(());
+	}
+
+	fn remove(&self, link_path: &Path) -> io::Result<()> {
+		return remove_path(link_path);
+	}
+}
+
+impl LinkBackend for BatchBackend {
+	fn link_name(&self, name: &str, _source_path: &Path) -> String {
+		return format!("{}.bat", name);
+	}
+
+	fn create(&self, link_path: &Path, source_path: &Path) -> io::Result<()> {
+		require_file(LinkKind::Batch, source_path)?;
+		self.remove(link_path)?;
+		let mut file = File::create(link_path)?;
+		file.write_all(batch_shim(source_path).as_bytes())?;
+		return Ok(());
+	}
+
+	/// refuses to remove a batch file which pman did not write
+	fn remove(&self, link_path: &Path) -> io::Result<()> {
+		if fs::symlink_metadata(link_path).map(|m| m.is_file()).unwrap_or(false) {
+			let mut contents = Vec::new();
+			File::open(link_path)?.read_to_end(&mut contents)?;
+			if !is_batch_shim(&String::from_utf8_lossy(&contents)) {
+				return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("\"{}\" was not created by pman, remove it by hand if its no longer needed", link_path.display())));
+			}
+		}
+		return remove_path(link_path);
+	}
+}
+
+impl LinkBackend for ShellBackend {
+	fn link_name(&self, name: &str, _source_path: &Path) -> String {
+		return name.to_owned();
+	}
+
+	/// exec replaces the shell so signals and the exit code go straight to the target
+	fn create(&self, link_path: &Path, source_path: &Path) -> io::Result<()> {
+		require_file(LinkKind::Shell, source_path)?;
+		remove_path(link_path)?;
+		let mut file = File::create(link_path)?;
+		file.write_all(format!("#!/bin/sh\nexec {} \"$@\"\n", sh_quote(&source_path.to_string_lossy())).as_bytes())?;
+		drop(file);
+		return set_executable(link_path);
+	}
+
+	fn remove(&self, link_path: &Path) -> io::Result<()> {
+		return remove_path(link_path);
+	}
+}
+
+impl LinkBackend for PowerShellBackend {
+	fn link_name(&self, name: &str, _source_path: &Path) -> String {
+		return format!("{}.ps1", name);
+	}
+
+	fn create(&self, link_path: &Path, source_path: &Path) -> io::Result<()> {
+		require_file(LinkKind::PowerShell, source_path)?;
+		remove_path(link_path)?;
+		let mut file = File::create(link_path)?;
+		file.write_all(format!("& {} @args\r\nexit $LASTEXITCODE\r\n", ps_quote(&source_path.to_string_lossy())).as_bytes())?;
+		return Ok(());
+	}
+
+	fn remove(&self, link_path: &Path) -> io::Result<()> {
+		return remove_path(link_path);
+	}
+}
+
+impl LinkBackend for DispatchBackend {
+	/// the dispatcher is an executable, windows needs the .exe to run it
+	fn link_name(&self, name: &str, _source_path: &Path) -> String {
+		return format!("{}{}", name, env::consts::EXE_SUFFIX);
+	}
+
+	/// every dispatch link is the same dispatcher, it finds out what to run from the name its run as.
+	/// Hard links are tried first, they dont take any space
+	fn create(&self, link_path: &Path, source_path: &Path) -> io::Result<()> {
+		require_file(LinkKind::Dispatch, source_path)?;
+		let dispatcher = dispatcher_path()?;
+		remove_path(link_path)?;
+		if fs::hard_link(&dispatcher, link_path).is_err() {
+			fs::copy(&dispatcher, link_path)?;
+		}
+		return Ok(());
+	}
+
+	fn remove(&self, link_path: &Path) -> io::Result<()> {
+		return remove_path(link_path);
+	}
+}
+
+/// the dispatcher executable, PMAN_DISPATCHER or pman-shim next to the running executable
+pub fn dispatcher_path() -> io::Result<PathBuf> {
+	if let Some(path) = env::var_os(DISPATCHER_ENV).filter(|v| !v.is_empty()) {
+		return Ok(PathBuf::from(path));
+	}
+	let path = env::current_exe()?.with_file_name(format!("pman-shim{}", env::consts::EXE_SUFFIX));
+	if !path.is_file() {
+		return Err(io::Error::new(io::ErrorKind::NotFound, format!("The dispatcher \"{}\" doesnt exist, install pman-shim next to pman or set {}", path.display(), DISPATCHER_ENV)));
+	}
+	return Ok(path);
+}
+
+/// removes whatever is at path without following symlinks, a missing path is not an error
+pub(crate) fn remove_path(path: &Path) -> io::Result<()> {
+	match fs::symlink_metadata(path) {
+		Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+		Err(e) => return Err(e),
+		Ok(meta) => {
+			if is_dir_entry(&meta) {
+				return fs::remove_dir(path);
+			}
+			return fs::remove_file(path);
+		}
+	}
+}
+
+/// windows keeps directory symlinks as directories, they need remove_dir
+#[cfg(windows)]
+fn is_dir_entry(meta: &fs::Metadata) -> bool {
+	return meta.is_dir() || meta.file_type().is_symlink_dir();
+}
+
+/// unix symlinks are always removed as files, only real directories need remove_dir
+#[cfg(not(windows))]
+fn is_dir_entry(meta: &fs::Metadata) -> bool {
+	return meta.is_dir();
+}
+
+/// errors if source_path is a directory, only symlinks can point at directories
+fn require_file(kind: LinkKind, source_path: &Path) -> io::Result<()> {
+	if source_path.is_dir() {
+		return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{} links can only point at files, \"{}\" is a directory", kind.as_str(), source_path.display())));
+	}
+	return Ok(());
+}
+
+#[cfg(windows)]
+fn symlink(source_path: &Path, link_path: &Path) -> io::Result<()> {
+	if source_path.is_dir() {
+		return symlink_dir(source_path, link_path);
+	}
+	return symlink_file(source_path, link_path);
+}
+
+#[cfg(unix)]
+fn symlink(source_path: &Path, link_path: &Path) -> io::Result<()> {
+	return ::std::os::unix::fs::symlink(source_path, link_path);
+}
+
+/// windows decides what can run by extension, so links straight to a file keep the files extension. eg: java => java.exe
+#[cfg(windows)]
+fn with_source_ext(name: &str, source_path: &Path) -> String {
+	if !source_path.is_dir() {
+		if let Some(ext) = source_path.extension() {
+			let ext = ext.to_string_lossy();
+			if Path::new(name).extension().map_or(true, |e| !e.to_string_lossy().eq_ignore_ascii_case(&ext)) {
+				return format!("{}.{}", name, ext);
+			}
+		}
+	}
+	return name.to_owned();
+}
+
+/// unix runs files by their execute bit, the name is used as is
+#[cfg(not(windows))]
+fn with_source_ext(name: &str, _source_path: &Path) -> String {
+	return name.to_owned();
+}
+
+#[cfg(unix)]
+fn set_executable(path: &Path) -> io::Result<()> {
+	let mut perms = fs::metadata(path)?.permissions();
+	perms.set_mode(0o755);
+	return fs::set_permissions(path, perms);
+}
+
+#[cfg(not(unix))]
+fn set_executable(_path: &Path) -> io::Result<()> {
+	return Ok(());
+}
+
+/// contents of a batch shim which runs source_path with all arguments and passes its exit code back.
+/// delayed expansion is turned off so a ! in the path is taken literally
+pub fn batch_shim(source_path: &Path) -> String {
+	return format!("@echo off\r\n{}\r\nsetlocal DisableDelayedExpansion\r\n{} %*\r\nexit /b %ERRORLEVEL%\r\n",
+		BATCH_SHIM_MARKER, cmd_quote(&source_path.to_string_lossy()));
+}
+
+/// checks if the contents of a batch file are a shim written by pman, older versions wrote a single @"path" %* line
+pub fn is_batch_shim(contents: &str) -> bool {
+	if contents.lines().any(|l| l.trim() == BATCH_SHIM_MARKER) {
+		return true;
+	}
+	let trimmed = contents.trim();
+	return !trimmed.contains('\n') && trimmed.starts_with("@\"") && trimmed.ends_with("\" %*");
+}
+
+/// double quotes a value for a batch file. Inside quotes cmd.exe takes &, ^, | and the like literally,
+/// only % still expands and it is escaped by doubling it. Windows paths can never contain a "
+fn cmd_quote(val: &str) -> String {
+	return format!("\"{}\"", val.replace("%", "%%"));
+}
+
+/// single quotes a value for sh, the only thing which needs escaping inside single quotes is the single quote itself
+fn sh_quote(val: &str) -> String {
+	return format!("'{}'", val.replace("'", "'\\''"));
+}
+
+/// single quotes a value for powershell, single quotes are escaped by doubling them
+fn ps_quote(val: &str) -> String {
+	return format!("'{}'", val.replace("'", "''"));
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs::File;
+	use std::io::prelude::*;
+	use std::path::Path;
+	use super::*;
+	use test_dir::TestDir;
+
+	#[test]
+	fn batch_shim_quotes_path() {
+		let shim = batch_shim(Path::new(r"C:\Program Files\Java\jdk1.8.0\bin\java.exe"));
+		assert_eq!(shim, "@echo off\r\nrem pman-shim\r\nsetlocal DisableDelayedExpansion\r\n\"C:\\Program Files\\Java\\jdk1.8.0\\bin\\java.exe\" %*\r\nexit /b %ERRORLEVEL%\r\n");
+	}
+
+	#[test]
+	fn batch_shim_escapes_cmd_specials() {
+		let shim = batch_shim(Path::new(r"D:\a&b\50%\x^y\hi!\tool.exe"));
+		assert!(shim.contains("\"D:\\a&b\\50%%\\x^y\\hi!\\tool.exe\" %*\r\n"));
+		assert!(shim.contains("setlocal DisableDelayedExpansion"));
+	}
+
+	#[test]
+	fn batch_shim_detection() {
+		assert!(is_batch_shim(&batch_shim(Path::new(r"C:\java.exe"))));
+		assert!(is_batch_shim("@\"C:\\\\java.exe\" %*")); // written by older versions
+		assert!(!is_batch_shim("@echo off\r\njava.exe %*\r\n"));
+		assert!(!is_batch_shim(""));
+	}
+
+	#[test]
+	fn batch_backend_leaves_user_files() {
+		let dir = TestDir::new("test-batch");
+		let target = dir.join("tool.exe");
+		File::create(&target).unwrap();
+		let link_path = dir.join("tool.bat");
+		let backend = LinkKind::Batch.backend();
+
+		backend.create(&link_path, &target).unwrap();
+		let mut contents = String::new();
+		File::open(&link_path).unwrap().read_to_string(&mut contents).unwrap();
+		assert_eq!(contents, batch_shim(&target));
+		backend.remove(&link_path).unwrap();
+		assert!(!link_path.exists());
+
+		File::create(&link_path).unwrap().write_all(b"@echo users own script").unwrap();
+		assert!(backend.create(&link_path, &target).is_err());
+		assert!(backend.remove(&link_path).is_err());
+		assert!(link_path.exists());
+	}
+}
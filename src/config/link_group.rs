@@ -1,5 +1,8 @@
+use std::collections::BTreeMap;
+use std::io;
 use std::path::PathBuf;
 use config::*;
+use error::NotOwned;
 
 impl LinkGroup {
 	
@@ -7,8 +10,12 @@ impl LinkGroup {
 		return LinkGroup {
 			name: name,
 			priority: priority,
+			version: None,
+			aliases: Vec::new(),
 			base_path: String::new(),
-			links: Vec::new()
+			links: Vec::new(),
+			kind: None,
+			env: BTreeMap::new()
 		}
 	}
 	
@@ -44,21 +51,41 @@ impl LinkGroup {
 		return did_remove;
 	}
 	
-	pub fn activate(&self, in_dir: &PathBuf) {
+	/// the env vars of this group with ${base} in their values replaced by the base path, sorted by name
+	pub fn env_vars(&self) -> Vec<(String, String)> {
+		return self.env.iter().map(|(name, val)| (name.to_owned(), val.replace("${base}", &self.base_path))).collect();
+	}
+	
+	/// makes every link of this group in the command directory. Keeps going when a link fails, the first failure is
+	/// returned once all were tried
+	pub fn activate(&self, cmd_dir: &mut CmdDir) -> io::Result<()> {
 		let source_path = PathBuf::from(&self.base_path);
+		let mut result = Ok(());
 		for link in self.links.iter() {
-			if let Err(e) = link.create_link(&in_dir, &source_path) {
-				println!("Unable to create link: \"{}\", {}", link.name, e)
+			if let Err(e) = link.create_link(cmd_dir, &source_path, self.kind) {
+				if result.is_ok() { result = Err(_link_error("create", link, e)); }
 			}
 		}
+		return result;
 	}
 	
-	pub fn deactivate(&self, in_dir: &PathBuf) {
+	/// removes every link of this group from the command directory, like activate the first failure is returned
+	pub fn deactivate(&self, cmd_dir: &mut CmdDir) -> io::Result<()> {
 		let source_path = PathBuf::from(&self.base_path);
+		let mut result = Ok(());
 		for link in self.links.iter() {
-			if let Err(e) = link.remove_link(&in_dir, &source_path) {
-				println!("Unable to remove link: \"{}\", {}", link.name, e)
+			if let Err(e) = link.remove_link(cmd_dir, &source_path, self.kind) {
+				if result.is_ok() { result = Err(_link_error("remove", link, e)); }
 			}
 		}
+		return result;
+	}
+}
+
+/// names the link in e, ownership errors already name the file and have to stay intact to become link conflicts
+fn _link_error(action: &str, link: &Link, e: io::Error) -> io::Error {
+	if e.get_ref().is_some_and(|inner| inner.is::<NotOwned>()) {
+		return e;
 	}
-}
\ No newline at end of file
+	return io::Error::new(e.kind(), format!("Unable to {} link \"{}\", {}", action, link.name, e));
+}
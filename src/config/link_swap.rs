@@ -0,0 +1,276 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use config::*;
+use config::link_backend::remove_path;
+use error::{NotOwned, PmanError};
+
+/// numbers the swaps of this process, several can be staged at once and each needs a work directory of its own
+static SWAP_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+impl LinkSwap {
+
+	/// makes every link of new_group in the work directory, nothing in the command directory changes yet.
+	/// old_group is the group being replaced, its links are moved aside on commit. Fails if a link points at a file
+	/// which doesnt exist or would replace a file pman doesnt own
+	pub fn stage(cmd_dir: &CmdDir, old_group: Option<&LinkGroup>, new_group: &LinkGroup) -> io::Result<LinkSwap> {
+		let work_dir = cmd_dir.path().join(format!(".pman-swap-{}-{}", process::id(), SWAP_COUNT.fetch_add(1, Ordering::SeqCst)));
+		fs::create_dir_all(work_dir.join("new"))?;
+		fs::create_dir_all(work_dir.join("old"))?;
+
+		let mut swap = LinkSwap {
+			work_dir: work_dir,
+			old_links: Vec::new(),
+			staged: Vec::new(),
+			moved: Vec::new(),
+			committed: Vec::new(),
+			manifest_before: cmd_dir.manifest().clone()
+		};
+		if let Some(old_group) = old_group {
+			let old_source_dir = PathBuf::from(&old_group.base_path);
+			for link in old_group.links.iter() {
+				let (link_path, _, _) = link.made_link(cmd_dir, &old_source_dir, old_group.kind);
+				if let Err(e) = cmd_dir.check_owned(&link_path) {
+					let _ = fs::remove_dir_all(&swap.work_dir);
+					return Err(e);
+				}
+				swap.old_links.push(link_path);
+			}
+		}
+
+		let source_dir = PathBuf::from(&new_group.base_path);
+		for link in new_group.links.iter() {
+			if let Err(e) = swap._stage_link(cmd_dir, &source_dir, new_group.kind, link) {
+				let _ = fs::remove_dir_all(&swap.work_dir);
+				// ownership errors already name the file and have to stay intact to become link conflicts
+				if e.get_ref().is_some_and(|inner| inner.is::<NotOwned>()) {
+					return Err(e);
+				}
+				return Err(io::Error::new(e.kind(), format!("Unable to create link \"{}\", {}", link.name, e)));
+			}
+		}
+		return Ok(swap);
+	}
+
+	fn _stage_link(&mut self, cmd_dir: &CmdDir, source_dir: &PathBuf, group_kind: Option<LinkKind>, link: &Link) -> io::Result<()> {
+		let (link_path, source_path, kind) = link.rel_link(cmd_dir.path(), source_dir, group_kind);
+		if !source_path.exists() {
+			return Err(io::Error::new(io::ErrorKind::NotFound, format!("\"{}\" doesnt exist", source_path.display())));
+		}
+		// whatever is in the way gets replaced, so pman has to own it
+		cmd_dir.check_owned(&link_path)?;
+
+		let staged_path = self.work_dir.join("new").join(link_path.file_name().unwrap());
+		kind.backend().create(&staged_path, &source_path)?;
+		self.staged.push((staged_path, link_path, kind));
+		return Ok(());
+	}
+
+	/// moves the old links aside and the staged links into the command directory, then records the change in the manifest.
+	/// If any step fails everything done so far is undone
+	pub fn commit(&mut self, cmd_dir: &mut CmdDir) -> io::Result<()> {
+		if let Err(e) = self._commit(cmd_dir) {
+			if let Err(rollback_e) = self.rollback(cmd_dir) {
+				return Err(io::Error::new(e.kind(), format!("{}, undoing the swap failed too, {}", e, rollback_e)));
+			}
+			return Err(e);
+		}
+		return Ok(());
+	}
+
+	fn _commit(&mut self, cmd_dir: &mut CmdDir) -> io::Result<()> {
+		self.manifest_before = cmd_dir.manifest().clone();
+
+		let mut in_the_way = self.old_links.clone();
+		for (_, link_path, _) in self.staged.iter() {
+			if !in_the_way.contains(link_path) {
+				in_the_way.push(link_path.clone());
+			}
+		}
+		for link_path in in_the_way {
+			if fs::symlink_metadata(&link_path).is_ok() {
+				let aside_path = self.work_dir.join("old").join(link_path.file_name().unwrap());
+				fs::rename(&link_path, &aside_path)?;
+				cmd_dir.forget(&link_path);
+				self.moved.push((link_path, aside_path));
+			}
+		}
+
+		for (staged_path, link_path, kind) in self.staged.iter() {
+			fs::rename(staged_path, link_path)?;
+			self.committed.push(link_path.clone());
+			cmd_dir.record(link_path, *kind)?;
+		}
+		return cmd_dir.save();
+	}
+
+	/// undoes a commit, the new links are taken out of the command directory, the moved links put back and the manifest restored.
+	/// If that fails the work directory is kept so nothing which was moved aside gets lost
+	pub fn rollback(&mut self, cmd_dir: &mut CmdDir) -> io::Result<()> {
+		let mut result = Ok(());
+		while let Some(link_path) = self.committed.pop() {
+			if let Err(e) = remove_path(&link_path) {
+				if result.is_ok() { result = Err(e); }
+			}
+		}
+		while let Some((link_path, aside_path)) = self.moved.pop() {
+			if let Err(e) = fs::rename(&aside_path, &link_path) {
+				if result.is_ok() { result = Err(e); }
+			}
+		}
+		cmd_dir.set_manifest(self.manifest_before.clone());
+		if let Err(e) = cmd_dir.save() {
+			if result.is_ok() { result = Err(e); }
+		}
+
+		match result {
+			Ok(()) => {
+				let _ = fs::remove_dir_all(&self.work_dir);
+				return Ok(());
+			},
+			Err(e) => return Err(io::Error::new(e.kind(), format!("{}, the replaced links are kept in \"{}\"", e, self.work_dir.display())))
+		}
+	}
+
+	/// rolls back after e made the change fail, returns e with the rollback error added if undoing failed too
+	pub fn rollback_after(&mut self, cmd_dir: &mut CmdDir, e: PmanError) -> PmanError {
+		if let Err(rollback_e) = self.rollback(cmd_dir) {
+			return PmanError::Io(io::Error::new(rollback_e.kind(), format!("{}, undoing the swap failed too, {}", e, rollback_e)));
+		}
+		return e;
+	}
+
+	/// removes the work directory with the moved aside links, after this the swap cant be rolled back
+	pub fn finish(self) -> io::Result<()> {
+		return fs::remove_dir_all(&self.work_dir);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs;
+	use std::path::PathBuf;
+
+	use config::*;
+	use test_dir::TestDir;
+
+	/// command dir with jdk7 and jdk8 groups, both have java and javac. jdk7 is linked
+	fn setup(name: &str) -> (TestDir, LinkGroup, LinkGroup) {
+		let dir = TestDir::new(&format!("swap-{}", name));
+		fs::create_dir_all(dir.join("cmd")).unwrap();
+
+		let mut groups = Vec::new();
+		for &(jdk, pri) in [("jdk7", 170), ("jdk8", 180)].iter() {
+			fs::create_dir_all(dir.join(jdk)).unwrap();
+			let mut group = LinkGroup::new(String::from("java"), pri);
+			group.set_base_path(dir.join(jdk).to_str().unwrap().to_owned());
+			for bin in ["java", "javac"].iter() {
+				fs::write(dir.join(jdk).join(bin), "").unwrap();
+				let mut link = Link::new(bin.to_string(), bin.to_string());
+				link.kind = Some(LinkKind::Shell);
+				group.add_link(link);
+			}
+			groups.push(group);
+		}
+		let jdk8 = groups.pop().unwrap();
+		let jdk7 = groups.pop().unwrap();
+		jdk7.activate(&mut CmdDir::open(dir.join("cmd"), false).unwrap()).unwrap();
+		return (dir, jdk7, jdk8);
+	}
+
+	fn linked_to(dir: &PathBuf, name: &str) -> String {
+		let shim = fs::read_to_string(dir.join("cmd").join(name)).unwrap();
+		if shim.contains("jdk7") { return String::from("jdk7"); }
+		if shim.contains("jdk8") { return String::from("jdk8"); }
+		return shim;
+	}
+
+	/// files in the command directory, not counting the manifest
+	fn cmd_entries(dir: &PathBuf) -> usize {
+		return fs::read_dir(dir.join("cmd")).unwrap().count() - 1;
+	}
+
+	fn open(dir: &PathBuf) -> CmdDir {
+		return CmdDir::open(dir.join("cmd"), false).unwrap();
+	}
+
+	#[test]
+	fn swap_replaces_all_links() {
+		let (dir, jdk7, mut jdk8) = setup("commit");
+		jdk8.remove_link(&String::from("javac"));
+		let mut cmd_dir = open(&dir);
+
+		let mut swap = LinkSwap::stage(&cmd_dir, Some(&jdk7), &jdk8).unwrap();
+		assert_eq!(linked_to(&dir, "java"), "jdk7"); // staging doesnt touch the command directory
+		swap.commit(&mut cmd_dir).unwrap();
+		swap.finish().unwrap();
+
+		assert_eq!(linked_to(&dir, "java"), "jdk8");
+		assert!(!dir.join("cmd").join("javac").exists()); // old links the new group doesnt have are gone
+		assert!(!open(&dir).manifest().contains_key("javac"));
+		assert!(open(&dir).check_owned(&dir.join("cmd").join("java")).is_ok());
+		assert_eq!(cmd_entries(&dir), 1); // and so is the work directory
+	}
+
+	#[test]
+	fn failed_stage_changes_nothing() {
+		let (dir, jdk7, jdk8) = setup("stage");
+		fs::remove_file(dir.join("jdk8").join("javac")).unwrap();
+
+		assert!(LinkSwap::stage(&open(&dir), Some(&jdk7), &jdk8).is_err());
+		assert_eq!(linked_to(&dir, "java"), "jdk7");
+		assert_eq!(linked_to(&dir, "javac"), "jdk7");
+		assert_eq!(cmd_entries(&dir), 2);
+	}
+
+	#[test]
+	fn failed_commit_rolls_back() {
+		let (dir, jdk7, jdk8) = setup("rollback");
+		let mut cmd_dir = open(&dir);
+		let manifest = cmd_dir.manifest().clone();
+		let mut swap = LinkSwap::stage(&cmd_dir, Some(&jdk7), &jdk8).unwrap();
+		// lose the last staged link so moving it in fails half way through the commit
+		fs::remove_file(&swap.staged[1].0).unwrap();
+
+		assert!(swap.commit(&mut cmd_dir).is_err());
+		assert_eq!(open(&dir).manifest(), &manifest);
+		assert_eq!(linked_to(&dir, "java"), "jdk7");
+		assert_eq!(linked_to(&dir, "javac"), "jdk7");
+		assert_eq!(cmd_entries(&dir), 2);
+	}
+
+	#[test]
+	fn refuses_to_replace_unowned_files() {
+		let (dir, jdk7, mut jdk8) = setup("unowned");
+		fs::write(dir.join("cmd").join("jar"), "users own script").unwrap();
+		let mut jar = Link::new(String::from("jar"), String::from("java"));
+		jar.kind = Some(LinkKind::Shell);
+		jdk8.add_link(jar);
+
+		assert!(LinkSwap::stage(&open(&dir), Some(&jdk7), &jdk8).is_err());
+		assert_eq!(fs::read_to_string(dir.join("cmd").join("jar")).unwrap(), "users own script");
+		assert_eq!(linked_to(&dir, "java"), "jdk7");
+
+		let mut forced = CmdDir::open(dir.join("cmd"), true).unwrap();
+		let mut swap = LinkSwap::stage(&forced, Some(&jdk7), &jdk8).unwrap();
+		swap.commit(&mut forced).unwrap();
+		assert_eq!(linked_to(&dir, "jar"), "jdk8");
+	}
+
+	#[test]
+	fn committed_swap_rolls_back() {
+		let (dir, jdk7, jdk8) = setup("undo");
+		let mut cmd_dir = open(&dir);
+		let mut swap = LinkSwap::stage(&cmd_dir, Some(&jdk7), &jdk8).unwrap();
+		swap.commit(&mut cmd_dir).unwrap();
+		assert_eq!(linked_to(&dir, "javac"), "jdk8");
+
+		swap.rollback(&mut cmd_dir).unwrap();
+		assert_eq!(linked_to(&dir, "java"), "jdk7");
+		assert_eq!(linked_to(&dir, "javac"), "jdk7");
+		assert_eq!(cmd_entries(&dir), 2);
+	}
+}
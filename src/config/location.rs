@@ -0,0 +1,173 @@
+use std::env;
+use std::ffi::OsString;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use config::*;
+
+/// env var pointing at the config file to use, same as the --cfg flag
+pub const CONFIG_ENV: &'static str = "PMAN_CONFIG";
+
+/// env var pointing at the command directory to use, same as the --cmd flag
+pub const CMD_DIR_ENV: &'static str = "PMAN_CMD_DIR";
+
+impl Config {
+
+	/// the config file to use when no --cfg flag was given, see locate
+	pub fn cfg_path() -> Result<PathBuf, io::Error> {
+		return Ok(Config::locate(None)?.path);
+	}
+
+	/// the config file next to the executable, named ".{exe_name}". This is where configs were always kept and is still used
+	/// for portable installs
+	pub fn portable_cfg_path() -> Result<PathBuf, io::Error> {
+		let mut config_path = env::current_exe()?;
+		let mut config_name: String = String::from(".");
+		// i think i need to keep this as a OsString, but i cant fucking figure out how to prepend a "." to it... so fuck it, convert it to UTF-8 string
+		config_name.push_str(config_path.file_stem().unwrap().to_str().unwrap());
+
+		config_path.pop(); // remove the executable name
+		config_path.push(PathBuf::from(&config_name)); // add in config name which is ".{exe_name}"
+
+		return Ok(config_path);
+	}
+
+	/**
+	Finds the config file to use. flag is the path given with --cfg. The search order is:
+	the flag, the PMAN_CONFIG env var, $XDG_CONFIG_HOME/pman/config.toml, ~/.config/pman/config.toml and finally .pman next to the executable.
+	The flag and env var are used even if the file doesnt exist yet, the others only when the file exists. When no config exists
+	anywhere the first per user location is picked, so new configs dont need a writable install directory.
+	*/
+	pub fn locate(flag: Option<PathBuf>) -> Result<CfgLocation, io::Error> {
+		let candidates = Config::cfg_candidates(flag)?;
+		return Ok(Config::_pick(candidates, &|path| path.exists()));
+	}
+
+	/// every location a config file is searched at, in search order. Locations which dont apply, eg. no --cfg flag
+	/// or no home directory, are left out
+	pub fn cfg_candidates(flag: Option<PathBuf>) -> Result<Vec<CfgLocation>, io::Error> {
+		return Config::_candidates(flag, &|name| env::var_os(name), Config::portable_cfg_path()?);
+	}
+
+	fn _candidates(flag: Option<PathBuf>, env_var: &dyn Fn(&str) -> Option<OsString>, portable: PathBuf) -> Result<Vec<CfgLocation>, io::Error> {
+		// empty env vars count as not set
+		let non_empty = |name: &str| env_var(name).filter(|v| !v.is_empty()).map(PathBuf::from);
+
+		let mut candidates = Vec::new();
+		if let Some(path) = flag {
+			candidates.push(CfgLocation { path: path, source: CfgSource::Flag });
+		}
+		if let Some(path) = non_empty(CONFIG_ENV) {
+			candidates.push(CfgLocation { path: path, source: CfgSource::Env });
+		}
+		if let Some(dir) = non_empty("XDG_CONFIG_HOME") {
+			candidates.push(CfgLocation { path: dir.join("pman").join("config.toml"), source: CfgSource::XdgConfigHome });
+		}
+		// windows has no HOME, its user directory is USERPROFILE
+		if let Some(home) = non_empty("HOME").or_else(|| non_empty("USERPROFILE")) {
+			candidates.push(CfgLocation { path: home.join(".config").join("pman").join("config.toml"), source: CfgSource::HomeConfig });
+		}
+		candidates.push(CfgLocation { path: portable, source: CfgSource::Portable });
+
+		// relative flag and env paths are relative to the working directory
+		for candidate in candidates.iter_mut() {
+			if candidate.path.is_relative() && (candidate.source == CfgSource::Flag || candidate.source == CfgSource::Env) {
+				candidate.path = env::current_dir()?.join(&candidate.path);
+			}
+		}
+		return Ok(candidates);
+	}
+
+	fn _pick(candidates: Vec<CfgLocation>, exists: &dyn Fn(&PathBuf) -> bool) -> CfgLocation {
+		let mut first_per_user: Option<CfgLocation> = None;
+		for candidate in candidates {
+			match candidate.source {
+				CfgSource::Flag | CfgSource::Env => return candidate,
+				_ => {}
+			}
+			if exists(&candidate.path) {
+				return candidate;
+			}
+			if first_per_user.is_none() {
+				first_per_user = Some(candidate);
+			}
+		}
+		// there is always the portable location, so this only panics if that was left out of the candidates
+		return first_per_user.expect("no config file candidates");
+	}
+}
+
+impl CfgSource {
+
+	/// explains why a config at this location was picked
+	pub fn describe(&self) -> String {
+		match *self {
+			CfgSource::Flag => return String::from("given with the --cfg flag"),
+			CfgSource::Env => return format!("set by the {} env var", CONFIG_ENV),
+			CfgSource::XdgConfigHome => return String::from("per user config under $XDG_CONFIG_HOME"),
+			CfgSource::HomeConfig => return String::from("per user config under ~/.config"),
+			CfgSource::Portable => return String::from("next to the pman executable (portable mode)")
+		}
+	}
+}
+
+impl fmt::Display for CfgSource {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			CfgSource::Flag => write!(f, "--cfg"),
+			CfgSource::Env => write!(f, "{}", CONFIG_ENV),
+			CfgSource::XdgConfigHome => write!(f, "$XDG_CONFIG_HOME"),
+			CfgSource::HomeConfig => write!(f, "~/.config"),
+			CfgSource::Portable => write!(f, "portable")
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::ffi::OsString;
+	use std::path::PathBuf;
+
+	use config::*;
+
+	fn env_of(vars: &'static [(&'static str, &'static str)]) -> impl Fn(&str) -> Option<OsString> {
+		return move |name: &str| vars.iter().find(|&&(k, _)| k == name).map(|&(_, v)| OsString::from(v));
+	}
+
+	fn sources(candidates: &Vec<CfgLocation>) -> Vec<CfgSource> {
+		return candidates.iter().map(|c| c.source).collect();
+	}
+
+	#[test]
+	fn search_order() {
+		let env = env_of(&[("PMAN_CONFIG", "/env/pman.toml"), ("XDG_CONFIG_HOME", "/xdg"), ("HOME", "/home/me")]);
+		let candidates = Config::_candidates(Some(PathBuf::from("/flag.toml")), &env, PathBuf::from("/usr/bin/.pman")).unwrap();
+		assert_eq!(sources(&candidates), vec![CfgSource::Flag, CfgSource::Env, CfgSource::XdgConfigHome, CfgSource::HomeConfig, CfgSource::Portable]);
+		assert_eq!(candidates[2].path, PathBuf::from("/xdg/pman/config.toml"));
+		assert_eq!(candidates[3].path, PathBuf::from("/home/me/.config/pman/config.toml"));
+
+		// flags and env vars are picked even when the file doesnt exist yet
+		assert_eq!(Config::_pick(candidates.clone(), &|_| false).source, CfgSource::Flag);
+		assert_eq!(Config::_pick(candidates[1..].to_vec(), &|_| false).source, CfgSource::Env);
+
+		let empty_env = env_of(&[("PMAN_CONFIG", ""), ("HOME", "/home/me")]);
+		let candidates = Config::_candidates(None, &empty_env, PathBuf::from("/usr/bin/.pman")).unwrap();
+		assert_eq!(sources(&candidates), vec![CfgSource::HomeConfig, CfgSource::Portable]);
+	}
+
+	#[test]
+	fn existing_configs_are_preferred() {
+		let env = env_of(&[("XDG_CONFIG_HOME", "/xdg"), ("HOME", "/home/me")]);
+		let candidates = Config::_candidates(None, &env, PathBuf::from("/usr/bin/.pman")).unwrap();
+
+		// an existing portable config keeps working
+		let portable = Config::_pick(candidates.clone(), &|p| p == &PathBuf::from("/usr/bin/.pman"));
+		assert_eq!(portable.source, CfgSource::Portable);
+		let home = Config::_pick(candidates.clone(), &|p| p.starts_with("/home/me"));
+		assert_eq!(home.source, CfgSource::HomeConfig);
+
+		// new configs go to the first per user location
+		assert_eq!(Config::_pick(candidates, &|_| false).source, CfgSource::XdgConfigHome);
+	}
+}
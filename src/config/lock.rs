@@ -0,0 +1,110 @@
+use std::fs;
+use std::fs::OpenOptions;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use config::*;
+use error::PmanError;
+
+/// how long to wait for another pman to release the config before giving up
+pub const LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+
+impl ConfigLock {
+	/// the lock file
+	pub fn path(&self) -> &PathBuf {
+		return &self.path;
+	}
+
+	/// false when there was nothing to lock, because there is no config yet or the config directory is read only
+	pub fn is_held(&self) -> bool {
+		return self.file.is_some();
+	}
+}
+
+impl Config {
+
+	/// path of the lock file for the config file
+	pub fn lock_path(config_path: &Path) -> PathBuf {
+		return Config::sibling_path(config_path, ".lock");
+	}
+
+	/**
+	Locks the config file, waiting up to timeout for another pman to release it. Errors with PmanError::Locked on timeout.
+	When there is no config file yet there is nothing to protect, the first one is written atomically and nothing is made here
+	for it. Neither is there when the lock file cant be made because the config directory is read only, the config cant be
+	written either. An empty lock is returned for both.
+	*/
+	pub fn lock(config_path: &Path, timeout: Duration) -> Result<ConfigLock, PmanError> {
+		let path = Config::lock_path(config_path);
+		if !config_path.exists() {
+			return Ok(ConfigLock {
+				file: None,
+				path: path
+			});
+		}
+
+		let file;
+		match OpenOptions::new().read(true).write(true).create(true).truncate(false).open(&path) {
+			Ok(f) => file = f,
+			Err(ref e) if e.kind() == io::ErrorKind::PermissionDenied || e.kind() == io::ErrorKind::ReadOnlyFilesystem => {
+				return Ok(ConfigLock {
+					file: None,
+					path: path
+				});
+			},
+			Err(e) => return Err(PmanError::from(e))
+		}
+
+		let start = Instant::now();
+		loop {
+			match file.try_lock() {
+				Ok(()) => {
+					return Ok(ConfigLock {
+						file: Some(file),
+						path: path
+					});
+				},
+				Err(fs::TryLockError::WouldBlock) => {
+					if start.elapsed() >= timeout {
+						return Err(PmanError::Locked(path));
+					}
+					thread::sleep(Duration::from_millis(50));
+				},
+				Err(fs::TryLockError::Error(e)) => return Err(PmanError::from(e))
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+
+	use config::*;
+	use error::PmanError;
+	use test_dir::TestDir;
+
+	#[test]
+	fn lock_excludes_other_runs() {
+		let dir = TestDir::new("lock");
+		let cfg_path = dir.join(".pman");
+		let missing = Config::lock(&cfg_path, Duration::from_millis(0)).unwrap();
+		assert!(!missing.is_held());
+		assert!(!missing.path().exists()); // nothing is made for a config which doesnt exist
+		Config::new(String::from("cmd")).write_to(&cfg_path).unwrap();
+
+		let lock = Config::lock(&cfg_path, Duration::from_millis(0)).unwrap();
+		assert!(lock.path().exists());
+		match Config::lock(&cfg_path, Duration::from_millis(100)) {
+			Err(PmanError::Locked(ref path)) => assert_eq!(path, lock.path()),
+			_ => panic!("expected the config to be locked")
+		}
+
+		drop(lock);
+		assert!(Config::lock(&cfg_path, Duration::from_millis(0)).is_ok());
+	}
+}
@@ -1,28 +1,268 @@
+use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
 
 mod config;
 mod link_group;
 mod link;
+mod link_backend;
+mod legacy;
+mod format;
+mod selector;
+mod location;
+mod backup;
+mod lock;
+mod link_swap;
+mod cmd_dir;
+mod versions;
+mod env_script;
+mod profile;
+mod semver;
+mod alias;
+mod mode;
+
+pub use self::location::{CONFIG_ENV, CMD_DIR_ENV};
+pub use self::backup::BACKUP_COUNT;
+pub use self::lock::LOCK_TIMEOUT;
+pub use self::cmd_dir::{MANIFEST_NAME, CONFIG_POINTER};
+pub use self::link_backend::DISPATCHER_ENV;
+pub use self::versions::VERSIONS_FILE;
+pub use self::env_script::ENV_SCRIPT;
 
 
 /// the configuration for the entire path manager, saves to file
 pub struct Config {
 	config_map: HashMap<String, Vec<LinkGroup>>,
 	active_configs: HashMap<String, u64>, // (entry.name, entry.priority)
+	previous_configs: HashMap<String, u64>, // the priority which was active before the last swap of the name
+	modes: HashMap<String, GroupMode>, // names without one are in manual mode, see mode.rs
 	links: Vec<Link>,
-	cmd_dir_str: String
+	profiles: BTreeMap<String, BTreeMap<String, GroupSelector>>, // profile name -> group name -> selector, see profile.rs
+	cmd_dir_str: String,
+	cmd_dir_override: Option<PathBuf> // used instead of cmd_dir_str for this run only, never saved
 }
 
-#[derive(Clone, Debug)]
+// field order matters for the toml output, plain values have to come before the tables (links)
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct LinkGroup {
 	pub name: String,
 	pub base_path: String,
-	pub links: Vec<Link>,
-	pub priority: u64
+	pub priority: u64,
+	/// version of whats in the group eg. 1.8.0_20, when set it orders the groups instead of the priority. See cmp_rank
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub version: Option<String>,
+	/// names the group can be selected by besides its priority, eg. lts. An alias is on one group of the name at most
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub aliases: Vec<String>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub kind: Option<LinkKind>, // default kind for the links in this group, None uses the platform default
+	/// env vars set while the group is active, ${base} in a value is replaced with the base path. See env_vars
+	#[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+	pub env: BTreeMap<String, String>,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub links: Vec<Link>
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Link {
 	pub name: String,
-	pub path: String
-}
\ No newline at end of file
+	pub path: String,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub kind: Option<LinkKind> // None falls back to the groups kind, then the platform default
+}
+
+/// where a config file came from, see Config::locate for the order they are searched in
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CfgSource {
+	/// the --cfg flag
+	Flag,
+	/// the PMAN_CONFIG env var
+	Env,
+	/// $XDG_CONFIG_HOME/pman/config.toml
+	XdgConfigHome,
+	/// ~/.config/pman/config.toml
+	HomeConfig,
+	/// .pman next to the executable
+	Portable
+}
+
+/// a config file path and why it was picked
+#[derive(Clone, Debug, PartialEq)]
+pub struct CfgLocation {
+	pub path: PathBuf,
+	pub source: CfgSource
+}
+
+/// Advisory lock on a config file, held from reading the config until its written back so two pman runs cant overwrite
+/// each others changes. The lock is a "{config}.lock" file next to the config, its released when this is dropped.
+/// The lock file itself is left in place, removing it would let a waiting run lock a file which is about to be deleted
+pub struct ConfigLock {
+	file: Option<File>, // None when the config directory is read only, see Config::lock
+	path: PathBuf
+}
+
+/// picks one group out of the groups which share a name
+#[derive(Clone, Debug, PartialEq)]
+pub enum GroupSelector {
+	/// the highest group, see LinkGroup::cmp_rank
+	Highest,
+	/// the lowest group
+	Lowest,
+	/// the active group
+	Active,
+	/// the group which was active before the last swap
+	Previous,
+	/// the group which has the alias, see Config::set_alias
+	Alias(String),
+	/// the group with the lowest priority which is still >= the given priority
+	Closest(u64),
+	/// only the group with exactly the given priority
+	Exact(u64),
+	/// the group with the highest version which meets the requirement, groups without a version never do
+	Version(VersionReq)
+}
+
+/// A version of a group like 1.8.0, 17 or 1.7.0_79. The numbers can be separated by . or _, missing numbers count as 0
+/// so 17 and 17.0.0 are the same version. A pre release like 21-ea comes before the release, +build metadata is ignored
+#[derive(Clone, Debug)]
+pub struct Version {
+	parts: Vec<u64>,
+	pre: String, // empty for releases
+	text: String // as it was written
+}
+
+/// a requirement on the version of a group, eg. ^1.8, ~11 or >=17
+#[derive(Clone, Debug, PartialEq)]
+pub struct VersionReq {
+	op: VersionOp,
+	version: Version
+}
+
+/// how a VersionReq compares versions, see VersionReq::matches
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VersionOp {
+	/// ^1.8 any version up to the next change of the first non zero number, >=1.8 and <2. A bare 1.8 is the same
+	Caret,
+	/// ~1.8 any version up to the next minor version, >=1.8 and <1.9. ~11 is >=11 and <12
+	Tilde,
+	/// =1.8 versions which start with the given numbers, 1.8.0_20 matches but 1.9 doesnt
+	Exact,
+	Greater,
+	GreaterEq,
+	Less,
+	LessEq
+}
+
+/// how the active group of a name is picked, like the modes of update-alternatives
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupMode {
+	/// the highest group is kept active whenever a group of the name is added, changed or removed
+	Auto,
+	/// the group picked with swap stays active until another one is picked
+	Manual
+}
+
+/// A .pman-versions file. It pins the group used for a name in the directory its in and every directory below,
+/// one pin per line eg. "java 170". Blank lines and # comments are kept when the file is written back
+#[derive(Clone, Debug, PartialEq)]
+pub struct VersionFile {
+	path: PathBuf,
+	lines: Vec<String>,
+	pins: Vec<Pin>
+}
+
+/// the group one line of a .pman-versions file picks for a name
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pin {
+	pub name: String,
+	pub selector: GroupSelector,
+	/// 1 based line in the file
+	pub line: usize
+}
+
+/// the strategy used to place a link into the command directory
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum LinkKind {
+	#[serde(rename = "symlink")]
+	Symlink,
+	#[serde(rename = "hardlink")]
+	Hardlink,
+	#[serde(rename = "copy")]
+	Copy,
+	#[serde(rename = "bat")]
+	Batch,
+	#[serde(rename = "sh")]
+	Shell,
+	#[serde(rename = "ps1")]
+	PowerShell,
+	#[serde(rename = "dispatch")]
+	Dispatch
+}
+
+/// a shell pman writes env scripts for and prints env vars for, see Shell::render
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Shell {
+	/// sh, bash, zsh and friends
+	Sh,
+	Fish,
+	PowerShell,
+	/// cmd.exe
+	Cmd
+}
+
+/// creates and removes the file which represents a link in the command directory
+pub trait LinkBackend {
+	/// the file name the link named `name` will have in the command directory
+	fn link_name(&self, name: &str, source_path: &Path) -> String;
+	/// creates the link at link_path which points at source_path, overwrites whatever link is there
+	fn create(&self, link_path: &Path, source_path: &Path) -> io::Result<()>;
+	/// removes the link at link_path
+	fn remove(&self, link_path: &Path) -> io::Result<()>;
+}
+
+/// The command directory together with the manifest of the files pman made in it. Every link is made and removed through
+/// this so pman never replaces or deletes a file it didnt make, or one which was changed since, unless forced to
+pub struct CmdDir {
+	path: PathBuf,
+	manifest: BTreeMap<String, ManifestEntry>, // file name -> entry
+	has_manifest: bool, // false until the manifest file exists
+	force: bool // replace and remove files pman doesnt own
+}
+
+/// a file pman made in the command directory
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+	pub kind: LinkKind,
+	/// hash of the files contents when pman made it, for symlinks its the hash of where they point
+	pub hash: String
+}
+
+/// what is at a path in the command directory, as far as the manifest knows
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Ownership {
+	/// nothing is there
+	Missing,
+	/// pman made it and it hasnt changed since
+	Owned,
+	/// pman made it but it was changed since
+	Modified,
+	/// pman didnt make it
+	Unowned
+}
+
+/// Swaps the links of a group in the command directory for the links of another group as one change. The new links are made
+/// in a hidden work directory in the command directory first, then the old links are moved aside and the new ones moved in.
+/// Anything which fails on the way undoes the whole swap
+pub struct LinkSwap {
+	work_dir: PathBuf,
+	old_links: Vec<PathBuf>, // links of the group being replaced
+	staged: Vec<(PathBuf, PathBuf, LinkKind)>, // (staged path, link path, kind)
+	moved: Vec<(PathBuf, PathBuf)>, // (link path, path it was moved aside to)
+	committed: Vec<PathBuf>, // link paths the staged links were moved to
+	manifest_before: BTreeMap<String, ManifestEntry> // the manifest before the commit, put back on rollback
+}
@@ -0,0 +1,32 @@
+use config::*;
+
+impl GroupMode {
+
+	pub fn all() -> [GroupMode; 2] {
+		return [GroupMode::Auto, GroupMode::Manual];
+	}
+
+	pub fn as_str(&self) -> &'static str {
+		match *self {
+			GroupMode::Auto => return "auto",
+			GroupMode::Manual => return "manual"
+		}
+	}
+
+	pub fn parse(val: &str) -> Option<GroupMode> {
+		return GroupMode::all().iter().find(|m| m.as_str().eq_ignore_ascii_case(val)).cloned();
+	}
+}
+
+/// The mode of a name says who picks its active group, see Manager::apply_mode for auto mode. Names without a recorded
+/// mode are in manual mode, thats how every name behaved before there were modes
+impl Config {
+
+	pub fn mode(&self, name: &String) -> GroupMode {
+		return self.modes.get(name).cloned().unwrap_or(GroupMode::Manual);
+	}
+
+	pub fn set_mode(&mut self, name: &String, mode: GroupMode) {
+		self.modes.insert(name.clone(), mode);
+	}
+}
@@ -0,0 +1,97 @@
+use std::collections::BTreeMap;
+
+use config::*;
+use error::PmanError;
+
+/// Profiles are named sets of groups which are used together, eg. java 170 with maven 330. A profile maps group names
+/// to the selector which picks the group of that name, see Manager::use_profile
+impl Config {
+
+	/// every profile by name
+	pub fn profiles(&self) -> &BTreeMap<String, BTreeMap<String, GroupSelector>> {
+		return &self.profiles;
+	}
+
+	pub fn profile(&self, profile: &String) -> Option<&BTreeMap<String, GroupSelector>> {
+		return self.profiles.get(profile);
+	}
+
+	/// saves the active groups as the profile, replacing it if it exists. With names only the groups of those names are
+	/// saved. The exact priorities are saved so the profile brings back this state
+	pub fn save_profile(&mut self, profile: &String, names: Option<&[String]>) {
+		let mut selectors = BTreeMap::new();
+		for (name, priority) in self.active_configs.iter() {
+			if names.is_none_or(|names| names.contains(name)) {
+				selectors.insert(name.clone(), GroupSelector::Exact(*priority));
+			}
+		}
+		self.profiles.insert(profile.clone(), selectors);
+	}
+
+	/// makes the profile select the group of name with selector, the profile is made if it doesnt exist
+	pub fn set_profile_group(&mut self, profile: &String, name: &String, selector: GroupSelector) {
+		self.profiles.entry(profile.clone()).or_default().insert(name.clone(), selector);
+	}
+
+	/// removes the profile, returns false if there was none of that name
+	pub fn remove_profile(&mut self, profile: &String) -> bool {
+		return self.profiles.remove(profile).is_some();
+	}
+
+	/// the groups the profile selects, ordered by name. Errors with InvalidArgs if there is no such profile and with
+	/// MissingGroup if one of its selectors doesnt match a group
+	pub fn profile_groups(&self, profile: &String) -> Result<Vec<LinkGroup>, PmanError> {
+		let selectors = self.profiles.get(profile).ok_or_else(|| PmanError::InvalidArgs(format!("No profile named {}", profile)))?;
+		let mut groups = Vec::new();
+		for (name, selector) in selectors.iter() {
+			let group = self.select(name, selector).ok_or_else(|| {
+				PmanError::MissingGroup {
+					name: name.to_owned(),
+					selector: selector.to_string()
+				}
+			})?;
+			groups.push(group);
+		}
+		return Ok(groups);
+	}
+
+	/// checks if the groups the profile selects are the active ones. Profiles which dont exist or select nothing dont match
+	pub fn profile_matches(&self, profile: &String) -> bool {
+		match self.profile_groups(profile) {
+			Ok(groups) => return !groups.is_empty() && groups.iter().all(|g| self.is_active(g)),
+			Err(_) => return false
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use config::*;
+
+	#[test]
+	fn profiles_capture_the_active_groups() {
+		let mut config = Config::new(String::from("cmd"));
+		for &(name, pri) in [("java", 170), ("java", 180), ("mvn", 330), ("node", 6)].iter() {
+			config.insert_config_entry(LinkGroup::new(String::from(name), pri)).unwrap();
+		}
+		let (java, mvn, legacy) = (String::from("java"), String::from("mvn"), String::from("legacy"));
+		config.set_active(&java, &170);
+		config.set_active(&mvn, &330);
+
+		config.save_profile(&legacy, None);
+		assert_eq!(config.profile(&legacy).unwrap().get(&java), Some(&GroupSelector::Exact(170)));
+		assert!(config.profile_matches(&legacy));
+		config.set_active(&java, &180);
+		assert!(!config.profile_matches(&legacy));
+		let groups = config.profile_groups(&legacy).unwrap();
+		assert_eq!(groups.iter().map(|g| g.priority).collect::<Vec<u64>>(), vec![170, 330]);
+
+		config.save_profile(&String::from("java"), Some(::std::slice::from_ref(&java)));
+		assert_eq!(config.profile(&java).unwrap().len(), 1);
+		config.set_profile_group(&legacy, &String::from("node"), GroupSelector::Closest(8));
+		assert!(config.profile_groups(&legacy).is_err());
+		assert!(config.profile_groups(&String::from("none")).is_err());
+		assert!(config.remove_profile(&legacy));
+		assert!(!config.remove_profile(&legacy));
+	}
+}
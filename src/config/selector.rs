@@ -0,0 +1,73 @@
+use std::fmt;
+
+use config::*;
+
+impl GroupSelector {
+	
+	/// selector for a priority given on the command line, negative priorities select the highest group
+	pub fn from_priority(priority: i64, exact: bool) -> GroupSelector {
+		if priority < 0 {
+			return GroupSelector::Highest;
+		} else if exact {
+			return GroupSelector::Exact(priority as u64);
+		} else {
+			return GroupSelector::Closest(priority as u64);
+		}
+	}
+
+	/// parses a selector as its written in files, see spec. Plain numbers are priorities, anything else which starts
+	/// with a number or an op is a version requirement. Other names which start with a letter are aliases
+	pub fn parse(spec: &str) -> Option<GroupSelector> {
+		match spec.to_ascii_lowercase().as_ref() {
+			"highest" => return Some(GroupSelector::Highest),
+			"lowest" => return Some(GroupSelector::Lowest),
+			"active" => return Some(GroupSelector::Active),
+			"previous" => return Some(GroupSelector::Previous),
+			_ => {}
+		}
+		if let Some(pri) = spec.strip_prefix('=').and_then(|pri| pri.parse::<u64>().ok()) {
+			return Some(GroupSelector::Exact(pri));
+		}
+		if let Ok(pri) = spec.parse::<u64>() {
+			return Some(GroupSelector::Closest(pri));
+		}
+		if let Some(req) = VersionReq::parse(spec) {
+			return Some(GroupSelector::Version(req));
+		}
+		if spec.starts_with(|c: char| c.is_ascii_alphabetic()) && spec.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+			return Some(GroupSelector::Alias(spec.to_owned()));
+		}
+		return None;
+	}
+
+	/// the selector as its written in files, "highest", "lowest", "active", "previous", "170" for the closest group,
+	/// "=170" for exactly 170, a version requirement like "^1.8" or an alias. Unlike to_string this tells closest and
+	/// exact apart
+	pub fn spec(&self) -> String {
+		match *self {
+			GroupSelector::Highest => return String::from("highest"),
+			GroupSelector::Lowest => return String::from("lowest"),
+			GroupSelector::Active => return String::from("active"),
+			GroupSelector::Previous => return String::from("previous"),
+			GroupSelector::Alias(ref alias) => return alias.to_owned(),
+			GroupSelector::Closest(pri) => return pri.to_string(),
+			GroupSelector::Exact(pri) => return format!("={}", pri),
+			GroupSelector::Version(ref req) => return req.to_string()
+		}
+	}
+}
+
+impl fmt::Display for GroupSelector {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			GroupSelector::Highest => write!(f, "highest"),
+			GroupSelector::Lowest => write!(f, "lowest"),
+			GroupSelector::Active => write!(f, "active"),
+			GroupSelector::Previous => write!(f, "previous"),
+			GroupSelector::Alias(ref alias) => write!(f, "{}", alias),
+			GroupSelector::Closest(pri) => write!(f, "{}", pri),
+			GroupSelector::Exact(pri) => write!(f, "{}", pri),
+			GroupSelector::Version(ref req) => write!(f, "{}", req)
+		}
+	}
+}
@@ -0,0 +1,281 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use config::*;
+
+impl Version {
+
+	/// parses a version like 1.8.0, v17, 1.7.0_79, 21-ea or 17.0.2+8. None if it doesnt start with a number
+	pub fn parse(text: &str) -> Option<Version> {
+		let text = text.trim();
+		let rest = text.strip_prefix(['v', 'V']).unwrap_or(text);
+		let end = rest.find(|c: char| !c.is_ascii_digit() && c != '.' && c != '_').unwrap_or(rest.len());
+		let (numbers, suffix) = rest.split_at(end);
+
+		let mut parts = Vec::new();
+		for part in numbers.split(['.', '_']) {
+			parts.push(part.parse::<u64>().ok()?); // also fails on empty parts, eg. 1..8 or a trailing dot
+		}
+		let pre;
+		if let Some(tag) = suffix.strip_prefix('-') {
+			pre = tag.split('+').next().unwrap_or("");
+			if pre.is_empty() {
+				return None;
+			}
+		} else if suffix.is_empty() || suffix.starts_with('+') {
+			pre = "";
+		} else {
+			return None;
+		}
+		return Some(Version {
+			parts: parts,
+			pre: pre.to_owned(),
+			text: text.to_owned()
+		});
+	}
+
+	/// the version as it was written
+	pub fn as_str(&self) -> &str {
+		return &self.text;
+	}
+
+	/// the numbers of the version, eg. [1, 7, 0, 79] for 1.7.0_79
+	pub fn parts(&self) -> &[u64] {
+		return &self.parts;
+	}
+
+	fn _part(&self, i: usize) -> u64 {
+		return self.parts.get(i).cloned().unwrap_or(0);
+	}
+
+	/// compares only the numbers, missing numbers count as 0
+	fn _cmp_parts(&self, parts: &[u64]) -> Ordering {
+		for i in 0..self.parts.len().max(parts.len()) {
+			let ord = self._part(i).cmp(&parts.get(i).cloned().unwrap_or(0));
+			if ord != Ordering::Equal {
+				return ord;
+			}
+		}
+		return Ordering::Equal;
+	}
+}
+
+/// compares pre release tags, dot separated identifiers which are compared as numbers when both are numbers
+fn _cmp_pre(a: &str, b: &str) -> Ordering {
+	match (a.is_empty(), b.is_empty()) {
+		(true, true) => return Ordering::Equal,
+		(true, false) => return Ordering::Greater, // a release comes after its pre releases
+		(false, true) => return Ordering::Less,
+		(false, false) => {}
+	}
+	let mut a_ids = a.split('.');
+	let mut b_ids = b.split('.');
+	loop {
+		let ord = match (a_ids.next(), b_ids.next()) {
+			(None, None) => return Ordering::Equal,
+			(None, Some(_)) => return Ordering::Less,
+			(Some(_), None) => return Ordering::Greater,
+			(Some(x), Some(y)) => match (x.parse::<u64>(), y.parse::<u64>()) {
+				(Ok(x), Ok(y)) => x.cmp(&y),
+				_ => x.cmp(y)
+			}
+		};
+		if ord != Ordering::Equal {
+			return ord;
+		}
+	}
+}
+
+impl Ord for Version {
+	fn cmp(&self, other: &Version) -> Ordering {
+		return self._cmp_parts(&other.parts).then_with(|| _cmp_pre(&self.pre, &other.pre));
+	}
+}
+
+impl PartialOrd for Version {
+	fn partial_cmp(&self, other: &Version) -> Option<Ordering> {
+		return Some(self.cmp(other));
+	}
+}
+
+// 17 and 17.0 are the same version, so equality has to agree with the ordering rather than the text
+impl PartialEq for Version {
+	fn eq(&self, other: &Version) -> bool {
+		return self.cmp(other) == Ordering::Equal;
+	}
+}
+
+impl Eq for Version {}
+
+impl fmt::Display for Version {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}", self.text)
+	}
+}
+
+impl VersionOp {
+
+	/// every op, the ones which start with another have to come first for parsing
+	fn all() -> [VersionOp; 7] {
+		return [VersionOp::GreaterEq, VersionOp::LessEq, VersionOp::Greater, VersionOp::Less,
+			VersionOp::Caret, VersionOp::Tilde, VersionOp::Exact];
+	}
+
+	pub fn as_str(&self) -> &'static str {
+		match *self {
+			VersionOp::Caret => return "^",
+			VersionOp::Tilde => return "~",
+			VersionOp::Exact => return "=",
+			VersionOp::Greater => return ">",
+			VersionOp::GreaterEq => return ">=",
+			VersionOp::Less => return "<",
+			VersionOp::LessEq => return "<="
+		}
+	}
+}
+
+impl VersionReq {
+
+	pub fn new(op: VersionOp, version: Version) -> VersionReq {
+		return VersionReq {
+			op: op,
+			version: version
+		};
+	}
+
+	/// parses a requirement like ^1.8, ~11, >=17 or =1.8.0_20. A version without an op is a caret requirement
+	pub fn parse(spec: &str) -> Option<VersionReq> {
+		let spec = spec.trim();
+		for op in VersionOp::all().iter() {
+			if let Some(rest) = spec.strip_prefix(op.as_str()) {
+				return Version::parse(rest).map(|v| VersionReq::new(*op, v));
+			}
+		}
+		return Version::parse(spec).map(|v| VersionReq::new(VersionOp::Caret, v));
+	}
+
+	pub fn op(&self) -> VersionOp {
+		return self.op;
+	}
+
+	pub fn version(&self) -> &Version {
+		return &self.version;
+	}
+
+	/// checks if version meets the requirement
+	pub fn matches(&self, version: &Version) -> bool {
+		let req = &self.version;
+		match self.op {
+			VersionOp::Caret | VersionOp::Tilde => {
+				// the bound only compares numbers so pre releases of it, like 2.0.0-ea for ^1.8, dont match either
+				return version >= req && version._cmp_parts(&self._upper_bound()) == Ordering::Less;
+			},
+			VersionOp::Exact => {
+				return (0..req.parts.len()).all(|i| version._part(i) == req.parts[i])
+					&& (req.pre.is_empty() || _cmp_pre(&version.pre, &req.pre) == Ordering::Equal);
+			},
+			VersionOp::Greater => return version > req,
+			VersionOp::GreaterEq => return version >= req,
+			VersionOp::Less => return version < req,
+			VersionOp::LessEq => return version <= req
+		}
+	}
+
+	/// first version past a caret or tilde requirement, eg. 2 for ^1.8, 0.4 for ^0.3 and 1.9 for ~1.8
+	fn _upper_bound(&self) -> Vec<u64> {
+		let parts = &self.version.parts;
+		let bump;
+		if self.op == VersionOp::Tilde {
+			bump = if parts.len() > 1 { 1 } else { 0 };
+		} else {
+			bump = parts.iter().position(|p| *p != 0).unwrap_or(parts.len() - 1);
+		}
+		let mut bound = parts[..bump + 1].to_vec();
+		bound[bump] += 1;
+		return bound;
+	}
+}
+
+impl fmt::Display for VersionReq {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}{}", self.op.as_str(), self.version)
+	}
+}
+
+impl LinkGroup {
+
+	/// the version of the group, None if it has none or it cant be parsed
+	pub fn parsed_version(&self) -> Option<Version> {
+		return self.version.as_ref().and_then(|v| Version::parse(v));
+	}
+
+	/// Orders groups from lowest to highest. Groups with a version are ordered by it and come after every group without
+	/// one, which are ordered by priority. Groups of the same version are ordered by priority too
+	pub fn cmp_rank(&self, other: &LinkGroup) -> Ordering {
+		return self.parsed_version().cmp(&other.parsed_version()).then(self.priority.cmp(&other.priority));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use config::*;
+
+	fn v(text: &str) -> Version {
+		return Version::parse(text).unwrap();
+	}
+
+	fn req(spec: &str) -> VersionReq {
+		return VersionReq::parse(spec).unwrap();
+	}
+
+	#[test]
+	fn versions_order_like_semver() {
+		assert!(v("1.8.0_20") > v("1.8.0"));
+		assert!(v("1.8.0_20") < v("1.8.0_100"));
+		assert!(v("1.10") > v("1.9.9"));
+		assert_eq!(v("17"), v("v17.0.0"));
+		assert_eq!(v("17.0.2+8"), v("17.0.2"));
+		assert!(v("21-ea") < v("21"));
+		assert!(v("21-ea.10") > v("21-ea.9"));
+		for bad in ["", "jdk17", "1..8", "1.", "8u292", "17-"].iter() {
+			assert!(Version::parse(bad).is_none(), "{} parsed", bad);
+		}
+	}
+
+	#[test]
+	fn requirements_match_versions() {
+		assert!(req("^1.8").matches(&v("1.8.0_20")));
+		assert!(req("1.8").matches(&v("1.9")));
+		assert!(!req("^1.8").matches(&v("2.0.0-ea")));
+		assert!(!req("^0.3").matches(&v("0.4")));
+		assert!(req("~11").matches(&v("11.0.2")));
+		assert!(!req("~1.8").matches(&v("1.9")));
+		assert!(req(">=17").matches(&v("21")));
+		assert!(!req("<17").matches(&v("17.0.0")));
+		assert!(req("=1.8").matches(&v("1.8.0_20")));
+		assert!(!req("=1.8.0_20").matches(&v("1.8.0_21")));
+		assert_eq!(req(">= 17").to_string(), ">=17");
+		assert!(VersionReq::parse(">=").is_none());
+	}
+
+	#[test]
+	fn versioned_groups_outrank_the_others() {
+		let mut config = Config::new(String::from("cmd"));
+		for &(pri, version) in [(100, None), (170, Some("1.7.0_79")), (180, Some("1.8.0_20")), (110, Some("11.0.2"))].iter() {
+			let mut group = LinkGroup::new(String::from("java"), pri);
+			group.version = version.map(String::from);
+			config.insert_config_entry(group).unwrap();
+		}
+		let java = String::from("java");
+		let pris: Vec<u64> = config.config_entrys_by_name(&java).unwrap().iter().map(|g| g.priority).collect();
+		assert_eq!(pris, vec![110, 180, 170, 100]);
+		assert_eq!(config.select_highest_group(&java).unwrap().priority, 110);
+		assert_eq!(config.select(&java, &GroupSelector::parse("^1.7").unwrap()).unwrap().priority, 180);
+		assert_eq!(config.select(&java, &GroupSelector::parse("~1.7").unwrap()).unwrap().priority, 170);
+		assert!(config.select(&java, &GroupSelector::parse(">=17").unwrap()).is_none());
+		// numbers are still priorities, the lowest version at or above it is picked
+		assert_eq!(config.select(&java, &GroupSelector::parse("120").unwrap()).unwrap().priority, 170);
+		assert_eq!(config.select(&java, &GroupSelector::parse("105").unwrap()).unwrap().priority, 170);
+		assert_eq!(config.select(&java, &GroupSelector::parse("0").unwrap()), config.select_lowest_group(&java));
+	}
+}
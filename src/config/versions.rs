@@ -0,0 +1,184 @@
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::path::Path;
+use std::path::PathBuf;
+
+use config::*;
+use error::PmanError;
+
+/// name of the file which pins groups per directory
+pub const VERSIONS_FILE: &'static str = ".pman-versions";
+
+impl VersionFile {
+
+	/// an empty versions file in dir, nothing is written until write is called
+	pub fn new(dir: &Path) -> VersionFile {
+		return VersionFile {
+			path: dir.join(VERSIONS_FILE),
+			lines: Vec::new(),
+			pins: Vec::new()
+		};
+	}
+
+	/// reads the versions file at path. Errors with ConfigParse on lines which arent "<name> <selector>"
+	pub fn read(path: &Path) -> Result<VersionFile, PmanError> {
+		let mut contents = String::new();
+		File::open(path)?.read_to_string(&mut contents)?;
+
+		let mut file = VersionFile {
+			path: path.to_owned(),
+			lines: Vec::new(),
+			pins: Vec::new()
+		};
+		for (i, line) in contents.lines().enumerate() {
+			file.lines.push(line.to_owned());
+			let trimmed = line.trim();
+			if trimmed == "" || trimmed.starts_with('#') {
+				continue;
+			}
+			let parts: Vec<&str> = trimmed.split_whitespace().collect();
+			let selector = if parts.len() == 2 { GroupSelector::parse(parts[1]) } else { None };
+			match selector {
+				Some(selector) => file.pins.push(Pin {
+					name: parts[0].to_owned(),
+					selector: selector,
+					line: i+1
+				}),
+				None => return Err(PmanError::ConfigParse {
+					line: i+1,
+					message: format!("expected \"<name> <priority>\" in \"{}\", got \"{}\"", path.display(), trimmed)
+				})
+			}
+		}
+		return Ok(file);
+	}
+
+	/// every versions file in dir and the directories above it, nearest first
+	pub fn find_all(dir: &Path) -> Result<Vec<VersionFile>, PmanError> {
+		let mut files = Vec::new();
+		let mut next = Some(dir);
+		while let Some(dir) = next {
+			let path = dir.join(VERSIONS_FILE);
+			if path.is_file() {
+				files.push(VersionFile::read(&path)?);
+			}
+			next = dir.parent();
+		}
+		return Ok(files);
+	}
+
+	/// the versions file in dir, or an empty one if there is none yet
+	pub fn open(dir: &Path) -> Result<VersionFile, PmanError> {
+		let path = dir.join(VERSIONS_FILE);
+		if path.exists() {
+			return VersionFile::read(&path);
+		}
+		return Ok(VersionFile::new(dir));
+	}
+
+	pub fn path(&self) -> &PathBuf {
+		return &self.path;
+	}
+
+	pub fn pins(&self) -> &Vec<Pin> {
+		return &self.pins;
+	}
+
+	/// the pin for name in this file
+	pub fn get(&self, name: &str) -> Option<&Pin> {
+		return self.pins.iter().find(|p| p.name == name);
+	}
+
+	/// pins name to selector, the line of an existing pin is replaced so the rest of the file stays as it was
+	pub fn pin(&mut self, name: &str, selector: GroupSelector) {
+		let text = format!("{} {}", name, selector.spec());
+		if let Some(pin) = self.pins.iter_mut().find(|p| p.name == name) {
+			pin.selector = selector;
+			self.lines[pin.line-1] = text;
+			return;
+		}
+		self.lines.push(text);
+		self.pins.push(Pin {
+			name: name.to_owned(),
+			selector: selector,
+			line: self.lines.len()
+		});
+	}
+
+	/// removes the pin for name, returns false if there was none
+	pub fn unpin(&mut self, name: &str) -> bool {
+		let line = match self.get(name) {
+			Some(pin) => pin.line,
+			None => return false
+		};
+		self.lines.remove(line-1);
+		self.pins.retain(|p| p.name != name);
+		for pin in self.pins.iter_mut() {
+			if pin.line > line {
+				pin.line -= 1;
+			}
+		}
+		return true;
+	}
+
+	/// writes the file through a temp file, a file without pins or comments left is removed
+	pub fn write(&self) -> io::Result<()> {
+		if self.lines.iter().all(|l| l.trim() == "") {
+			match fs::remove_file(&self.path) {
+				Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+				result => return result
+			}
+		}
+		let tmp_path = Config::sibling_path(&self.path, ".tmp");
+		{
+			let mut file = File::create(&tmp_path)?;
+			for line in self.lines.iter() {
+				writeln!(file, "{}", line)?;
+			}
+			file.sync_all()?;
+		}
+		return fs::rename(&tmp_path, &self.path);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs;
+
+	use config::*;
+	use error::PmanError;
+	use test_dir::TestDir;
+
+	#[test]
+	fn pins_keep_the_rest_of_the_file() {
+		let dir = TestDir::new("versions");
+		fs::create_dir_all(dir.join("sub").join("deeper")).unwrap();
+		fs::write(dir.join(VERSIONS_FILE), "# jdks of this repo\njava 170\n\nnode highest\n").unwrap();
+
+		let mut file = VersionFile::open(&dir).unwrap();
+		assert_eq!(file.get("java").unwrap().selector, GroupSelector::Closest(170));
+		assert_eq!(file.get("node").unwrap().selector, GroupSelector::Highest);
+		file.pin("java", GroupSelector::Exact(180));
+		file.pin("mvn", GroupSelector::Closest(3));
+		assert!(file.unpin("node"));
+		file.write().unwrap();
+		assert_eq!(fs::read_to_string(dir.join(VERSIONS_FILE)).unwrap(), "# jdks of this repo\njava =180\n\nmvn 3\n");
+
+		// found from below, nearest first
+		VersionFile::new(&dir.join("sub")).write().unwrap(); // empty files arent written
+		let mut sub = VersionFile::new(&dir.join("sub"));
+		sub.pin("java", GroupSelector::Closest(110));
+		sub.write().unwrap();
+		let files = VersionFile::find_all(&dir.join("sub").join("deeper")).unwrap();
+		assert_eq!(files[0].path(), &dir.join("sub").join(VERSIONS_FILE));
+		assert_eq!(files[1].get("java").unwrap().selector, GroupSelector::Exact(180));
+
+		fs::write(dir.join(VERSIONS_FILE), "java\n").unwrap();
+		match VersionFile::read(&dir.join(VERSIONS_FILE)) {
+			Err(PmanError::ConfigParse { line, .. }) => assert_eq!(line, 1),
+			_ => panic!("expected a parse error")
+		}
+	}
+}
@@ -0,0 +1,259 @@
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use config::*;
+use error::PmanError;
+use manager::Manager;
+use pins::check_group_name;
+
+/// what discover does with one directory it found
+#[derive(Clone, Debug, PartialEq)]
+pub enum Discovered {
+	/// a new group for the directory
+	Add(LinkGroup),
+	/// the directory isnt added, reason says why eg. its already a group
+	Skip { dir: PathBuf, reason: String }
+}
+
+impl Manager {
+
+	/// Plans the groups of name for the directories in dir whose name matches pattern, see glob_match. The version of
+	/// a group comes from the directory name, eg. 1.7.0_79 for jdk1.7.0_79, its priority from the version, see
+	/// version_priority. The groups are copies of template with the directory joined to the base path of the template,
+	/// so a template with base path "bin" links out of the bin directory of every install. Directories which are
+	/// already groups of name, have no version in their name or are missing a link of the template are skipped.
+	/// Nothing is changed, see add_discovered
+	pub fn discover(&self, name: &String, dir: &Path, pattern: &str, template: &LinkGroup) -> Result<Vec<Discovered>, PmanError> {
+		let mut dirs = Vec::new();
+		for entry in fs::read_dir(dir)? {
+			let entry = entry?;
+			if entry.file_type()?.is_dir() && glob_match(pattern, &entry.file_name().to_string_lossy()) {
+				dirs.push(entry.path());
+			}
+		}
+		dirs.sort();
+
+		let groups = self.config().config_entrys_by_name(name).cloned().unwrap_or_default();
+		let mut taken: Vec<u64> = groups.iter().map(|g| g.priority).collect();
+		let mut plan = Vec::new();
+		for install_dir in dirs {
+			let base = if template.base_path.is_empty() { install_dir.clone() } else { install_dir.join(&template.base_path) };
+			let base_path = base.to_string_lossy().into_owned();
+			let skip = |reason: String| Discovered::Skip { dir: install_dir.clone(), reason: reason };
+
+			if let Some(group) = groups.iter().find(|g| Path::new(&g.base_path) == base) {
+				plan.push(skip(format!("its already {} {}", group.name, group.priority)));
+				continue;
+			}
+			let version = match _version_in(&install_dir.file_name().unwrap_or_default().to_string_lossy()) {
+				Some(version) => version,
+				None => {
+					plan.push(skip(String::from("theres no version in its name")));
+					continue;
+				}
+			};
+			if let Some(link) = template.links.iter().find(|l| !base.join(&l.path).exists()) {
+				plan.push(skip(format!("it has no {}", base.join(&link.path).display())));
+				continue;
+			}
+
+			// versions which only differ past the third number would share a priority
+			let mut priority = version_priority(&version);
+			while taken.contains(&priority) {
+				priority += 1;
+			}
+			taken.push(priority);
+			let mut group = template.clone();
+			group.name = name.to_owned();
+			group.priority = priority;
+			group.version = Some(version.as_str().to_owned());
+			group.aliases = Vec::new();
+			group.set_base_path(base_path);
+			plan.push(Discovered::Add(group));
+		}
+		return Ok(plan);
+	}
+
+	/// Adds the groups discover planned, either all of them or none. They are checked before any is added, a group fails
+	/// like add_group does if its priority is taken or a link has the name of a standalone link. A name which had no groups
+	/// yet is put in auto mode like 'pman group -add' does, the highest group is activated for names in auto mode.
+	/// Returns the group which was activated
+	pub fn add_discovered(&mut self, plan: &[Discovered]) -> Result<Option<LinkGroup>, PmanError> {
+		let groups: Vec<&LinkGroup> = plan.iter().filter_map(|d| match *d {
+			Discovered::Add(ref group) => Some(group),
+			Discovered::Skip { .. } => None
+		}).collect();
+		for (i, group) in groups.iter().enumerate() {
+			check_group_name(&group.name)?;
+			self.check_links(group)?;
+			if self.config().select_group(&group.name, group.priority).is_some()
+				|| groups[..i].iter().any(|g| g.name == group.name && g.priority == group.priority) {
+				return Err(PmanError::DuplicatePriority { name: group.name.to_owned(), priority: group.priority });
+			}
+		}
+		let name = match groups.last() {
+			Some(group) => group.name.to_owned(),
+			None => return Ok(None)
+		};
+
+		let new_name = self.config().config_entrys_by_name(&name).is_none_or(|v| v.is_empty());
+		if new_name {
+			self.config_mut().set_mode(&name, GroupMode::Auto);
+		}
+		for group in groups.iter() {
+			self.config_mut().insert_config_entry((*group).clone())?;
+		}
+		if let Err(e) = self.save() {
+			for group in groups.iter() {
+				self.config_mut().remove_config_entry(&group.name, group.priority);
+			}
+			return Err(e);
+		}
+		return self.apply_mode(&name);
+	}
+}
+
+/// Priority for a version, the first three numbers each get two digits so 1.7.0_79 is 10700 and 17.0.2 is 170002.
+/// Numbers over 99 count as 99. The version orders the groups anyway, the priority only has to be unique
+pub fn version_priority(version: &Version) -> u64 {
+	let mut priority = 0;
+	for i in 0..3 {
+		priority = priority * 100 + version.parts().get(i).cloned().unwrap_or(0).min(99);
+	}
+	return priority;
+}
+
+/// matches name against a pattern where * matches any run of characters and ? any one character. On windows case
+/// is ignored like the file system does
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+	let fold = |s: &str| if cfg!(windows) { s.to_lowercase() } else { s.to_owned() };
+	let pattern: Vec<char> = fold(pattern).chars().collect();
+	let name: Vec<char> = fold(name).chars().collect();
+	// backtracks to the last * when the rest doesnt match
+	let (mut p, mut n) = (0, 0);
+	let mut star: Option<(usize, usize)> = None;
+	while n < name.len() {
+		if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+			p += 1;
+			n += 1;
+		} else if p < pattern.len() && pattern[p] == '*' {
+			star = Some((p, n));
+			p += 1;
+		} else if let Some((star_p, star_n)) = star {
+			p = star_p + 1;
+			n = star_n + 1;
+			star = Some((star_p, star_n + 1));
+		} else {
+			return false;
+		}
+	}
+	return pattern[p..].iter().all(|c| *c == '*');
+}
+
+/// the version in a directory name, the first run of numbers separated by . or _, eg. 1.7.0_79 in jdk1.7.0_79 or
+/// 3.9.6 in apache-maven-3.9.6-bin
+fn _version_in(dir_name: &str) -> Option<Version> {
+	let start = dir_name.find(|c: char| c.is_ascii_digit())?;
+	let rest = &dir_name[start..];
+	let mut end = rest.find(|c: char| !c.is_ascii_digit() && c != '.' && c != '_').unwrap_or(rest.len());
+	// a trailing separator isnt part of the version, eg. node-18.17.0_
+	while end > 0 && !rest[..end].ends_with(|c: char| c.is_ascii_digit()) {
+		end -= 1;
+	}
+	return Version::parse(&rest[..end]);
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs;
+
+	use error::PmanError;
+	use manager::Manager;
+	use super::*;
+	use test_dir::{TestDir, test_manager};
+
+	#[test]
+	fn globs_and_versions_in_names() {
+		assert!(glob_match("jdk*", "jdk1.8.0_20"));
+		assert!(glob_match("*jdk*", "openjdk-17"));
+		assert!(glob_match("jdk?.*", "jdk8.0"));
+		assert!(!glob_match("jdk*", "jre1.8.0"));
+		assert!(!glob_match("jdk?", "jdk17"));
+		assert!(glob_match("*", ""));
+
+		let version = |name: &str| _version_in(name).map(|v| v.as_str().to_owned());
+		assert_eq!(version("jdk1.7.0_79"), Some(String::from("1.7.0_79")));
+		assert_eq!(version("apache-maven-3.9.6-bin"), Some(String::from("3.9.6")));
+		assert_eq!(version("jdk-17.0.2+8"), Some(String::from("17.0.2")));
+		assert_eq!(version("jdk8u292"), Some(String::from("8")));
+		assert_eq!(version("jdk"), None);
+		assert_eq!(version_priority(&Version::parse("1.7.0_79").unwrap()), 10700);
+		assert_eq!(version_priority(&Version::parse("17").unwrap()), 170000);
+	}
+
+	/// links java from the bin directory of each installation
+	fn java_template() -> LinkGroup {
+		let mut template = LinkGroup::new(String::from("java"), 0);
+		template.set_base_path(String::from("bin"));
+		template.add_link(Link::new(String::from("java"), String::from("java")));
+		return template;
+	}
+
+	/// manager with jdk1.7.0_79 added at 170 and the plan for discovering the jdks installed next to it
+	fn planned(name: &str) -> (TestDir, Manager, Vec<Discovered>) {
+		let dir = TestDir::new(&format!("discover-{}", name));
+		for jdk in ["jdk1.7.0_79", "jdk1.8.0_20", "jdk1.8.0_40", "jdk-17.0.2", "jdk-docs"].iter() {
+			fs::create_dir_all(dir.join("java").join(jdk).join("bin")).unwrap();
+			fs::write(dir.join("java").join(jdk).join("bin").join("java"), "").unwrap();
+		}
+		fs::create_dir_all(dir.join("java").join("jdk1.6").join("bin")).unwrap(); // no java binary
+		fs::create_dir_all(dir.join("java").join("jre1.8.0")).unwrap();
+		fs::create_dir_all(dir.join("cmd")).unwrap();
+		let mut manager = test_manager(&dir);
+		let mut known = LinkGroup::new(String::from("java"), 170);
+		known.set_base_path(dir.join("java").join("jdk1.7.0_79").join("bin").to_str().unwrap().to_owned());
+		manager.add_group(known).unwrap();
+
+		let plan = manager.discover(&String::from("java"), &dir.join("java"), "jdk*", &java_template()).unwrap();
+		return (dir, manager, plan);
+	}
+
+	#[test]
+	fn discover_plans_new_installations() {
+		let (_dir, _, plan) = planned("plan");
+		let added: Vec<(u64, Option<String>)> = plan.iter().filter_map(|d| match *d {
+			Discovered::Add(ref g) => Some((g.priority, g.version.clone())),
+			_ => None
+		}).collect();
+		assert_eq!(added, vec![
+			(170002, Some(String::from("17.0.2"))),
+			(10800, Some(String::from("1.8.0_20"))),
+			(10801, Some(String::from("1.8.0_40")))
+		]);
+		assert_eq!(plan.len(), 6); // jdk-docs, jdk1.6 and jdk1.7.0_79 are skipped, jre1.8.0 doesnt match
+	}
+
+	#[test]
+	fn taken_priorities_add_nothing() {
+		let (dir, mut manager, plan) = planned("taken");
+		let java = String::from("java");
+		// a group added since the plan was made took the priority of the last one, so none of them are added
+		manager.add_group(LinkGroup::new(java.clone(), 10801)).unwrap();
+		match manager.add_discovered(&plan) {
+			Err(PmanError::DuplicatePriority { priority, .. }) => assert_eq!(priority, 10801),
+			r => panic!("expected a duplicate priority, got {:?}", r)
+		}
+		assert_eq!(Manager::load_from(dir.join(".pman")).unwrap().config().config_entrys_by_name(&java).unwrap().len(), 2);
+	}
+
+	#[test]
+	fn discovered_groups_are_added() {
+		let (dir, mut manager, plan) = planned("add");
+		let java = String::from("java");
+		assert!(manager.add_discovered(&plan).unwrap().is_none()); // java has a group already, so its in manual mode
+		assert_eq!(manager.config().config_entrys_by_name(&java).unwrap().len(), 4);
+		assert_eq!(manager.config().select_highest_group(&java).unwrap().priority, 170002);
+		assert!(manager.discover(&java, &dir.join("java"), "jdk*", &java_template()).unwrap().iter().all(|d| matches!(*d, Discovered::Skip { .. })));
+	}
+}
@@ -0,0 +1,88 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use error::PmanError;
+use manager::Manager;
+
+impl Manager {
+
+	/// the file the dispatcher runs for command when its started in dir. A standalone link named command wins, otherwise
+	/// the group name which has a link named command is looked up like pman current does, so PMAN_<NAME> env vars and
+	/// .pman-versions pins pick the group before the active one. Errors with MissingGroup if no group is selected and with
+	/// MissingLink if nothing links command or the selected group doesnt have it
+	pub fn dispatch_target(&self, command: &str, dir: &Path) -> Result<PathBuf, PmanError> {
+		let config = self.config();
+		let cmd_dir = config.cmd_dir();
+		let command = command.to_owned();
+
+		if let Some(link) = config.get_link(&command) {
+			let (_, target, _) = link.rel_link(&cmd_dir, &cmd_dir, None);
+			return Ok(target);
+		}
+
+		let name = match config.group_with_link(&command) {
+			Some(group) => group.name.to_owned(),
+			None => return Err(PmanError::MissingLink(command))
+		};
+		let selection = self.current_of(dir, &name)?;
+		let group = match selection.priority.and_then(|pri| config.select_group(&name, pri)) {
+			Some(group) => group,
+			None => return Err(PmanError::MissingGroup { name: name, selector: String::from("active") })
+		};
+		let link = group.get_link(&command).ok_or(PmanError::MissingLink(command))?;
+		let (_, target, _) = link.rel_link(&cmd_dir, &PathBuf::from(&group.base_path), group.kind);
+		return Ok(target);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs;
+
+	use config::*;
+	use error::PmanError;
+	use manager::Manager;
+	use test_dir::{TestDir, jdk_group, jdk_manager};
+
+	/// java groups jdk7 at 170 with java and javac and jdk8 at 180 with only java, none active. Also a standalone link
+	/// tool and a repo directory to dispatch in
+	fn dispatch_manager(name: &str) -> (TestDir, Manager) {
+		let dir = TestDir::new(&format!("dispatch-{}", name));
+		fs::create_dir_all(dir.join("repo")).unwrap();
+		let mut manager = jdk_manager(&dir, &[("jdk7", 170)], &["java", "javac"]);
+		manager.add_group(jdk_group(&dir, "jdk8", 180, &["java"])).unwrap();
+		manager.config_mut().add_link(Link::new(String::from("tool"), dir.join("tool.sh").to_str().unwrap().to_owned()));
+		return (dir, manager);
+	}
+
+	#[test]
+	fn dispatch_needs_a_selected_group() {
+		let (dir, manager) = dispatch_manager("none");
+		match manager.dispatch_target("java", &dir.join("repo")) {
+			Err(PmanError::MissingGroup { .. }) => {},
+			other => panic!("expected no group to be selected, got {:?}", other)
+		}
+	}
+
+	#[test]
+	fn dispatch_follows_the_selected_group() {
+		let (dir, mut manager) = dispatch_manager("active");
+		let repo = dir.join("repo");
+		manager.config_mut().set_active(&String::from("java"), &180);
+		assert_eq!(manager.dispatch_target("java", &repo).unwrap(), dir.join("jdk8").join("java"));
+		assert!(manager.dispatch_target("javac", &repo).is_err()); // jdk8 doesnt have javac
+
+		manager.pin(&repo, &String::from("java"), &GroupSelector::Exact(170)).unwrap();
+		assert_eq!(manager.dispatch_target("javac", &repo).unwrap(), dir.join("jdk7").join("javac"));
+	}
+
+	#[test]
+	fn dispatch_of_standalone_links() {
+		let (dir, manager) = dispatch_manager("standalone");
+		assert_eq!(manager.dispatch_target("tool", &dir.join("repo")).unwrap(), dir.join("tool.sh"));
+		match manager.dispatch_target("node", &dir.join("repo")) {
+			Err(PmanError::MissingLink(_)) => {},
+			other => panic!("expected nothing to link node, got {:?}", other)
+		}
+	}
+}
@@ -0,0 +1,455 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use config::*;
+use error::PmanError;
+use manager::Manager;
+
+/// how bad a finding of the doctor is, ordered from least to most severe
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+	/// worth knowing, nothing is broken
+	Info,
+	/// works for now but is likely to cause trouble
+	Warning,
+	/// something the config links to is broken
+	Error
+}
+
+/// a change the doctor can make by itself, only offered where it cant lose anything the user made
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Fix {
+	/// make a link which is missing from the command directory
+	CreateLink { link_path: PathBuf, source_path: PathBuf, kind: LinkKind },
+	/// remove a file pman made which nothing in the config links anymore
+	RemoveFile { link_path: PathBuf, kind: LinkKind },
+	/// drop a manifest entry whose file is gone
+	ForgetFile { link_path: PathBuf }
+}
+
+/// one problem the doctor found
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Finding {
+	pub severity: Severity,
+	/// what is wrong
+	pub message: String,
+	/// what to do about it
+	pub suggestion: String,
+	/// applied by doctor --fix, None when the fix is up to the user
+	pub fix: Option<Fix>
+}
+
+/// a file the config expects in the command directory and who links it
+struct Expected {
+	owner: String,
+	link_path: PathBuf,
+	source_path: PathBuf,
+	kind: LinkKind
+}
+
+impl Manager {
+
+	/// checks the config against the command directory. Looks at every standalone link, every active group, every file in
+	/// the command directory and whether the command directory is on the PATH. Findings come most severe first
+	pub fn diagnose(&self) -> Result<Vec<Finding>, PmanError> {
+		let cmd_dir = self.open_cmd_dir()?;
+		let mut findings = Vec::new();
+
+		let expected = self._expected_links(&cmd_dir, &mut findings);
+		self._check_collisions(&expected, &mut findings);
+		for (_, owners) in expected.iter() {
+			Manager::_check_link(&cmd_dir, &owners[0], &mut findings)?;
+		}
+		if cmd_dir.path().is_dir() {
+			Manager::_check_cmd_dir(&cmd_dir, &expected, &mut findings)?;
+		} else {
+			findings.push(Finding {
+				severity: Severity::Warning,
+				message: format!("The command directory \"{}\" doesnt exist", cmd_dir.path().display()),
+				suggestion: String::from("Run 'pman rebuild', it makes the directory and the links in it"),
+				fix: None
+			});
+		}
+		if !_on_path(cmd_dir.path(), env::var_os("PATH")) {
+			findings.push(Finding {
+				severity: Severity::Warning,
+				message: format!("The command directory \"{}\" is not on the PATH, nothing pman links can be run by name", cmd_dir.path().display()),
+				suggestion: _path_hint(cmd_dir.path()),
+				fix: None
+			});
+		}
+
+		// stable sort, so findings of the same severity stay in the order they were found
+		findings.sort_by_key(|f| ::std::cmp::Reverse(f.severity));
+		return Ok(findings);
+	}
+
+	/// applies the fix of a finding. Goes through the manifest like every other change, so a file which changed since
+	/// diagnose looked at it is left alone
+	pub fn apply_fix(&self, fix: &Fix) -> Result<(), PmanError> {
+		let mut cmd_dir = self.link_cmd_dir()?;
+		match *fix {
+			Fix::CreateLink { ref link_path, ref source_path, kind } => cmd_dir.create(link_path, source_path, kind)?,
+			Fix::RemoveFile { ref link_path, kind } => cmd_dir.remove(link_path, kind)?,
+			Fix::ForgetFile { ref link_path } => {
+				cmd_dir.forget(link_path);
+				cmd_dir.save()?;
+			}
+		}
+		return Ok(());
+	}
+
+	/// every link the config makes by link path, a path with more than one owner is a collision.
+	/// Active groups which are missing or whose base directory is gone are reported here
+	fn _expected_links(&self, cmd_dir: &CmdDir, findings: &mut Vec<Finding>) -> BTreeMap<PathBuf, Vec<Expected>> {
+		let config = self.config();
+		let dir = cmd_dir.path().clone();
+		let mut expected: BTreeMap<PathBuf, Vec<Expected>> = BTreeMap::new();
+
+		for link in config.links() {
+			let (link_path, source_path, kind) = link.rel_link(&dir, &dir, None);
+			expected.entry(link_path.clone()).or_default().push(Expected {
+				owner: format!("standalone link {}", link.name),
+				link_path: link_path,
+				source_path: source_path,
+				kind: kind
+			});
+		}
+
+		let mut names: Vec<&String> = config.active_configs().keys().collect();
+		names.sort();
+		for name in names {
+			let group = match config.active_config_entry(name) {
+				Some(group) => group,
+				None => {
+					findings.push(Finding {
+						severity: Severity::Error,
+						message: format!("Group {}/{} is marked active but doesnt exist", name, config.active_configs()[name]),
+						suggestion: format!("Activate another group with 'pman swap {}'", name),
+						fix: None
+					});
+					continue;
+				}
+			};
+			let source_dir = PathBuf::from(&group.base_path);
+			if !source_dir.is_dir() {
+				findings.push(Finding {
+					severity: Severity::Error,
+					message: format!("The directory of active group {}/{} \"{}\" doesnt exist", group.name, group.priority, group.base_path),
+					suggestion: format!("Swap to another group with 'pman swap {}', or point the group at the new directory with 'pman group {} {} -dir <dir>'", group.name, group.name, group.priority),
+					fix: None
+				});
+			}
+			for link in group.links.iter() {
+				let (link_path, source_path, kind) = link.rel_link(&dir, &source_dir, group.kind);
+				expected.entry(link_path.clone()).or_default().push(Expected {
+					owner: format!("group {}/{}", group.name, group.priority),
+					link_path: link_path,
+					source_path: source_path,
+					kind: kind
+				});
+			}
+		}
+		return expected;
+	}
+
+	/// links which end up at the same file in the command directory, and standalone links which would collide with a group
+	/// as soon as its activated
+	fn _check_collisions(&self, expected: &BTreeMap<PathBuf, Vec<Expected>>, findings: &mut Vec<Finding>) {
+		for (link_path, owners) in expected.iter() {
+			if owners.len() > 1 {
+				let names: Vec<&str> = owners.iter().map(|o| o.owner.as_ref()).collect();
+				findings.push(Finding {
+					severity: Severity::Error,
+					message: format!("{} all link \"{}\", only the last one made wins", names.join(", "), link_path.display()),
+					suggestion: String::from("Rename or remove all but one of the links"),
+					fix: None
+				});
+			}
+		}
+
+		let config = self.config();
+		for link in config.links() {
+			if let Some(group) = config.group_with_link(&link.name) {
+				if !config.is_active(group) {
+					findings.push(Finding {
+						severity: Severity::Warning,
+						message: format!("Standalone link {} has the same name as a link of group {}/{}", link.name, group.name, group.priority),
+						suggestion: String::from("Rename or remove one of them, activating the group will fail until then"),
+						fix: None
+					});
+				}
+			}
+		}
+	}
+
+	/// a link the config makes, its target has to exist and its file has to be the one pman made
+	fn _check_link(cmd_dir: &CmdDir, link: &Expected, findings: &mut Vec<Finding>) -> Result<(), PmanError> {
+		let ownership = cmd_dir.ownership(&link.link_path)?;
+		if fs::metadata(&link.source_path).is_err() {
+			let mut message = format!("The target of {} \"{}\" doesnt exist", link.owner, link.source_path.display());
+			if ownership != Ownership::Missing {
+				message = format!("{}, \"{}\" is dangling", message, link.link_path.display());
+			}
+			findings.push(Finding {
+				severity: Severity::Error,
+				message: message,
+				suggestion: String::from("Reinstall what it points at, or point the link at its new location"),
+				fix: None
+			});
+			return Ok(());
+		}
+
+		match ownership {
+			Ownership::Owned => {},
+			Ownership::Missing => findings.push(Finding {
+				severity: Severity::Warning,
+				message: format!("The link of {} \"{}\" is missing", link.owner, link.link_path.display()),
+				suggestion: String::from("Make it again with 'pman rebuild'"),
+				fix: Some(Fix::CreateLink {
+					link_path: link.link_path.clone(),
+					source_path: link.source_path.clone(),
+					kind: link.kind
+				})
+			}),
+			Ownership::Modified => findings.push(Finding {
+				severity: Severity::Warning,
+				message: format!("The link of {} \"{}\" was changed since pman made it", link.owner, link.link_path.display()),
+				suggestion: String::from("Keep your changes somewhere else and make it again with 'pman --force rebuild'"),
+				fix: None
+			}),
+			Ownership::Unowned => findings.push(Finding {
+				severity: Severity::Warning,
+				message: format!("\"{}\" is in the place of the link of {} but pman didnt make it", link.link_path.display(), link.owner),
+				suggestion: String::from("Move it out of the way, or replace it with 'pman --force rebuild'"),
+				fix: None
+			})
+		}
+		return Ok(());
+	}
+
+	/// files in the command directory no link in the config makes, and manifest entries whose file is gone
+	fn _check_cmd_dir(cmd_dir: &CmdDir, expected: &BTreeMap<PathBuf, Vec<Expected>>, findings: &mut Vec<Finding>) -> Result<(), PmanError> {
+		let mut entries: Vec<PathBuf> = Vec::new();
+		for entry in fs::read_dir(cmd_dir.path())? {
+			entries.push(entry?.path());
+		}
+		entries.sort();
+
+		for path in entries {
+			let file_name = path.file_name().map_or(String::new(), |n| n.to_string_lossy().into_owned());
+			if file_name.starts_with(".pman-swap-") {
+				findings.push(Finding {
+					severity: Severity::Warning,
+					message: format!("\"{}\" was left behind by a swap which didnt finish", path.display()),
+					suggestion: String::from("It may hold links which were moved aside, move back what you need and delete it"),
+					fix: None
+				});
+				continue;
+			}
+			// pmans own bookkeeping, the manifest and the config pointer
+			if file_name.starts_with(".pman-") || expected.contains_key(&path) {
+				continue;
+			}
+
+			match cmd_dir.ownership(&path)? {
+				Ownership::Owned => findings.push(Finding {
+					severity: Severity::Warning,
+					message: format!("\"{}\" is a stale link, nothing in the config links it anymore", path.display()),
+					suggestion: String::from("Remove it"),
+					fix: Some(Fix::RemoveFile {
+						link_path: path.clone(),
+						kind: cmd_dir.manifest()[&file_name].kind
+					})
+				}),
+				Ownership::Modified => findings.push(Finding {
+					severity: Severity::Warning,
+					message: format!("\"{}\" is a stale link which was changed since pman made it", path.display()),
+					suggestion: String::from("Remove it by hand if its no longer needed"),
+					fix: None
+				}),
+				Ownership::Unowned => findings.push(Finding {
+					severity: Severity::Info,
+					message: format!("\"{}\" wasnt made by pman", path.display()),
+					suggestion: String::from("Nothing to do if you put it there, pman leaves it alone"),
+					fix: None
+				}),
+				Ownership::Missing => {}
+			}
+		}
+
+		for file_name in cmd_dir.manifest().keys() {
+			let path = cmd_dir.path().join(file_name);
+			if fs::symlink_metadata(&path).is_err() && !expected.contains_key(&path) {
+				findings.push(Finding {
+					severity: Severity::Info,
+					message: format!("The manifest lists \"{}\" which was deleted", path.display()),
+					suggestion: String::from("Drop it from the manifest"),
+					fix: Some(Fix::ForgetFile { link_path: path })
+				});
+			}
+		}
+		return Ok(());
+	}
+}
+
+/// checks if dir is one of the directories in path_var, the value of PATH
+fn _on_path(dir: &Path, path_var: Option<OsString>) -> bool {
+	let path_var = match path_var {
+		Some(v) => v,
+		None => return false
+	};
+	return env::split_paths(&path_var).any(|entry| same_dir(&entry, dir));
+}
+
+/// checks if two paths are the same directory, also when they are spelled differently eg. through a symlink
+pub(crate) fn same_dir(a: &Path, b: &Path) -> bool {
+	if a == b {
+		return true;
+	}
+	match (fs::canonicalize(a), fs::canonicalize(b)) {
+		(Ok(a), Ok(b)) => return a == b,
+		_ => return false
+	}
+}
+
+#[cfg(windows)]
+fn _path_hint(dir: &Path) -> String {
+	return format!("Add it to the PATH in the system settings, or run: setx PATH \"%PATH%;{}\"", dir.display());
+}
+
+#[cfg(not(windows))]
+fn _path_hint(dir: &Path) -> String {
+	return format!("Add it to the PATH in your shell profile, eg: export PATH=\"{}:$PATH\"", dir.display());
+}
+
+impl fmt::Display for Severity {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			Severity::Info => f.pad("info"),
+			Severity::Warning => f.pad("warning"),
+			Severity::Error => f.pad("error")
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::env;
+	use std::ffi::OsString;
+	use std::fs;
+	use std::path::PathBuf;
+
+	use config::*;
+	use doctor::*;
+	use error::PmanError;
+	use manager::Manager;
+	use test_dir::{TestDir, test_manager};
+
+	/// manager with the standalone links tool, gone and stale made in the command directory. Then the target of gone is
+	/// uninstalled, the link of tool deleted and stale taken out of the config behind pmans back
+	fn broken_manager(name: &str) -> (TestDir, Manager) {
+		let dir = TestDir::new(&format!("doctor-{}", name));
+		fs::create_dir_all(dir.join("cmd")).unwrap();
+		let mut manager = test_manager(&dir);
+		for name in ["tool", "gone", "stale"].iter() {
+			fs::write(dir.join(name), "").unwrap();
+			let mut link = Link::new(name.to_string(), dir.join(name).to_str().unwrap().to_owned());
+			link.kind = Some(LinkKind::Shell);
+			manager.add_link(link).unwrap();
+		}
+		fs::remove_file(dir.join("gone")).unwrap();
+		fs::remove_file(dir.join("cmd").join("tool")).unwrap();
+		manager.config_mut().remove_link(&String::from("stale"));
+		return (dir, manager);
+	}
+
+	fn has(findings: &Vec<Finding>, severity: Severity, text: &str) -> bool {
+		return findings.iter().any(|f| f.severity == severity && f.message.contains(text));
+	}
+
+	fn apply_all(manager: &Manager) {
+		for finding in manager.diagnose().unwrap().iter() {
+			if let Some(ref fix) = finding.fix {
+				manager.apply_fix(fix).unwrap();
+			}
+		}
+	}
+
+	#[test]
+	fn finds_problems() {
+		let (dir, manager) = broken_manager("find");
+		fs::remove_file(dir.join("cmd").join(CONFIG_POINTER)).unwrap();
+
+		let findings = manager.diagnose().unwrap();
+		assert!(!dir.join("cmd").join(CONFIG_POINTER).exists()); // diagnosing only reads the command directory
+		assert_eq!(findings[0].severity, Severity::Error);
+		assert!(has(&findings, Severity::Error, "gone"));
+		assert!(has(&findings, Severity::Warning, "tool"));
+		assert!(has(&findings, Severity::Warning, "stale"));
+	}
+
+	#[test]
+	fn fixes_missing_and_stale_links() {
+		let (dir, manager) = broken_manager("fix");
+		apply_all(&manager);
+
+		let findings = manager.diagnose().unwrap();
+		assert!(!has(&findings, Severity::Warning, "tool"));
+		assert!(!has(&findings, Severity::Warning, "stale"));
+		assert!(dir.join("cmd").join("tool").exists());
+		assert!(!dir.join("cmd").join("stale").exists());
+	}
+
+	#[test]
+	fn fixes_leave_unowned_files_alone() {
+		let (dir, manager) = broken_manager("unowned");
+		fs::write(dir.join("cmd").join("mine"), "users own script").unwrap();
+		assert!(has(&manager.diagnose().unwrap(), Severity::Info, "mine"));
+		apply_all(&manager);
+		assert_eq!(fs::read_to_string(dir.join("cmd").join("mine")).unwrap(), "users own script");
+	}
+
+	#[test]
+	fn fix_refuses_files_put_in_the_way() {
+		// the user put a file where the missing link goes after diagnose looked
+		let (dir, manager) = broken_manager("refused");
+		let findings = manager.diagnose().unwrap();
+		let fix = findings.iter().filter_map(|f| f.fix.as_ref()).find(|fix| matches!(**fix, Fix::CreateLink { .. })).unwrap();
+		fs::write(dir.join("cmd").join("tool"), "users own script").unwrap();
+		match manager.apply_fix(fix) {
+			Err(PmanError::LinkConflict { .. }) => {},
+			r => panic!("expected a link conflict, got {:?}", r)
+		}
+		assert_eq!(fs::read_to_string(dir.join("cmd").join("tool")).unwrap(), "users own script");
+	}
+
+	#[test]
+	fn reports_a_missing_cmd_dir() {
+		let dir = TestDir::new("doctor-missing");
+		let manager = test_manager(&dir);
+
+		assert!(has(&manager.diagnose().unwrap(), Severity::Warning, "doesnt exist"));
+		assert!(!dir.join("cmd").exists());
+	}
+
+	#[test]
+	fn checks_the_path() {
+		let dir = TestDir::new("doctor-path");
+		let cmd = dir.join("cmd");
+		fs::create_dir_all(&cmd).unwrap();
+		let path_var = env::join_paths(vec![PathBuf::from("/usr/bin"), cmd.clone()]).unwrap();
+		assert!(_on_path(&cmd, Some(path_var)));
+		// the same directory through a different spelling still counts
+		assert!(_on_path(&cmd, Some(OsString::from(dir.join("cmd").join("..").join("cmd")))));
+		assert!(!_on_path(&cmd, Some(OsString::from("/usr/bin"))));
+		assert!(!_on_path(&cmd, None));
+	}
+}
@@ -0,0 +1,129 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::error::Error;
+
+/// Everything which can go wrong while running pman. The config and command code returns these instead of
+/// exiting, main turns them into a message and an exit code.
+///
+/// exit codes:
+///   0  success
+///   1  I/O error
+///   2  invalid command line usage
+///   3  pman is not configured yet
+///   4  the config file could not be parsed
+///   5  no group or link matched what was asked for
+///   6  a group already exists with that priority
+///   7  a link conflicts with another link or a file pman does not own
+///   8  another pman is running and holds the config lock
+///   9  pman doctor found errors
+/// pman exec exits with the exit code of the command it ran instead
+#[derive(Debug)]
+pub enum PmanError {
+	Io(io::Error),
+	/// bad arguments, the message says what was wrong
+	InvalidArgs(String),
+	NotConfigured,
+	/// line is 1 based, 0 when the line isnt known
+	ConfigParse { line: usize, message: String },
+	/// config was written by a newer pman with a format version this one doesnt understand
+	ConfigVersion(u32),
+	/// no group matched name with the selector, selector is whatever the user asked for eg. "170" or "highest"
+	MissingGroup { name: String, selector: String },
+	MissingLink(String),
+	DuplicatePriority { name: String, priority: u64 },
+	LinkConflict { name: String, reason: String },
+	/// timed out waiting for another pman to let go of the config, holds the lock file
+	Locked(PathBuf),
+	/// doctor found this many errors, they were printed already
+	Unhealthy(usize),
+	/// the command pman exec ran failed with this exit code, it reported why itself
+	Exited(i32)
+}
+
+impl PmanError {
+	/// exit code the process ends with for this error, see the list on PmanError
+	pub fn exit_code(&self) -> i32 {
+		match *self {
+			PmanError::Io(_) => return 1,
+			PmanError::InvalidArgs(_) => return 2,
+			PmanError::NotConfigured => return 3,
+			PmanError::ConfigParse { .. } | PmanError::ConfigVersion(_) => return 4,
+			PmanError::MissingGroup { .. } | PmanError::MissingLink(_) => return 5,
+			PmanError::DuplicatePriority { .. } => return 6,
+			PmanError::LinkConflict { .. } => return 7,
+			PmanError::Locked(_) => return 8,
+			PmanError::Unhealthy(_) => return 9,
+			PmanError::Exited(code) => return code
+		}
+	}
+}
+
+impl fmt::Display for PmanError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			PmanError::Io(ref e) => write!(f, "{}", e),
+			PmanError::InvalidArgs(ref msg) => write!(f, "{}", msg),
+			PmanError::NotConfigured => write!(f, "pman is not configured yet!\nRun 'pman help configure' for more information"),
+			PmanError::ConfigParse { line: 0, ref message } => write!(f, "Invalid configuration, {}", message),
+			PmanError::ConfigParse { line, ref message } => write!(f, "Invalid configuration at line {}, {}", line, message),
+			PmanError::ConfigVersion(version) => write!(f, "Configuration format version {} is newer than this pman understands, update pman", version),
+			PmanError::MissingGroup { ref name, ref selector } => write!(f, "No matching link group found for {}/{}", name, selector),
+			PmanError::MissingLink(ref name) => write!(f, "No link named \"{}\" found", name),
+			PmanError::DuplicatePriority { ref name, priority } => write!(f, "A group {}/{} already exists", name, priority),
+			PmanError::LinkConflict { ref name, ref reason } => write!(f, "Link \"{}\" conflicts, {}", name, reason),
+			PmanError::Locked(ref path) => write!(f, "Another pman is running, gave up waiting for it to release \"{}\"", path.display()),
+			PmanError::Unhealthy(count) => write!(f, "Found {} error(s) which need fixing", count),
+			PmanError::Exited(code) => write!(f, "The command exited with code {}", code)
+		}
+	}
+}
+
+impl Error for PmanError {
+	fn source(&self) -> Option<&(dyn Error + 'static)> {
+		match *self {
+			PmanError::Io(ref e) => return Some(e),
+			_ => return None
+		}
+	}
+}
+
+impl From<io::Error> for PmanError {
+	/// files pman refused to touch come wrapped in io errors from the link code, they are conflicts
+	fn from(e: io::Error) -> PmanError {
+		if let Some(not_owned) = e.get_ref().and_then(|inner| inner.downcast_ref::<NotOwned>()) {
+			return PmanError::LinkConflict {
+				name: not_owned.path.file_name().map_or(String::new(), |n| n.to_string_lossy().into_owned()),
+				reason: not_owned.to_string()
+			};
+		}
+		return PmanError::Io(e);
+	}
+}
+
+/// A file in the command directory pman refused to replace or remove, because pman didnt make it or it was changed since.
+/// Its passed around inside an io::Error with the kind AlreadyExists
+#[derive(Debug)]
+pub struct NotOwned {
+	pub path: PathBuf,
+	/// true if pman made the file but its contents changed since
+	pub modified: bool
+}
+
+impl NotOwned {
+	pub fn into_io(self) -> io::Error {
+		return io::Error::new(io::ErrorKind::AlreadyExists, self);
+	}
+}
+
+impl fmt::Display for NotOwned {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if self.modified {
+			write!(f, "\"{}\" was changed since pman made it, use --force to replace it anyway", self.path.display())
+		} else {
+			write!(f, "\"{}\" was not made by pman, use --force to replace it anyway", self.path.display())
+		}
+	}
+}
+
+impl Error for NotOwned {}
@@ -0,0 +1,160 @@
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process;
+use std::process::Command;
+
+use config::*;
+use error::PmanError;
+use manager::Manager;
+use pins::override_var;
+
+/// a group made available to single commands without activating it, see Manager::group_env. The links of the group
+/// are made in a directory of their own which is put first on the PATH, the directory is removed again on drop
+#[derive(Debug)]
+pub struct GroupEnv {
+	group: LinkGroup,
+	link_dir: PathBuf,
+	vars: Vec<(String, Option<OsString>)>
+}
+
+impl GroupEnv {
+
+	pub fn group(&self) -> &LinkGroup {
+		return &self.group;
+	}
+
+	/// the directory holding the links of the group
+	pub fn link_dir(&self) -> &PathBuf {
+		return &self.link_dir;
+	}
+
+	/// env vars to set for commands, PATH with the link directory first, the PMAN_<NAME> override of the group
+	/// so dispatch links and nested pman runs pick the same group, then the env vars of the group. Vars which only
+	/// other groups of the name set are None, they are removed
+	pub fn vars(&self) -> &Vec<(String, Option<OsString>)> {
+		return &self.vars;
+	}
+
+	/// a command running program in this environment
+	pub fn command(&self, program: &str) -> Command {
+		let mut cmd = Command::new(program);
+		for (name, val) in self.vars.iter() {
+			match *val {
+				Some(ref val) => cmd.env(name, val),
+				None => cmd.env_remove(name)
+			};
+		}
+		return cmd;
+	}
+}
+
+impl Drop for GroupEnv {
+	fn drop(&mut self) {
+		let _ = fs::remove_dir_all(&self.link_dir);
+	}
+}
+
+impl Manager {
+
+	/// makes the group of the given name which the selector picks available to single commands, the active group and the
+	/// command directory dont change. Dispatch links are made as the platform default kind, in the link directory the
+	/// dispatcher couldnt find the config
+	pub fn group_env(&self, name: &String, selector: &GroupSelector) -> Result<GroupEnv, PmanError> {
+		let group = self.config().select(name, selector).ok_or_else(|| {
+			PmanError::MissingGroup {
+				name: name.to_owned(),
+				selector: selector.to_string()
+			}
+		})?;
+		let link_dir = env::temp_dir().join(format!("pman-exec-{}-{}-{}", group.name, group.priority, process::id()));
+		let _ = fs::remove_dir_all(&link_dir);
+		fs::create_dir_all(&link_dir)?;
+
+		let mut path = vec![link_dir.clone()];
+		if let Some(path_var) = env::var_os("PATH") {
+			path.extend(env::split_paths(&path_var));
+		}
+		let path_var = env::join_paths(path).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+		let mut vars = vec![
+			(String::from("PATH"), Some(path_var)),
+			(override_var(&group.name), Some(OsString::from(GroupSelector::Exact(group.priority).spec())))
+		];
+		for (var, val) in self.config().env_for(name, Some(&group)) {
+			vars.push((var, val.map(OsString::from)));
+		}
+		let group_env = GroupEnv {
+			vars: vars,
+			group: group,
+			link_dir: link_dir
+		};
+
+		let source_dir = PathBuf::from(&group_env.group.base_path);
+		for link in group_env.group.links.iter() {
+			let (mut link_path, source_path, mut kind) = link.rel_link(&group_env.link_dir, &source_dir, group_env.group.kind);
+			if kind == LinkKind::Dispatch {
+				kind = LinkKind::platform_default();
+				link_path = group_env.link_dir.join(kind.backend().link_name(&link.name, &source_path));
+			}
+			kind.backend().create(&link_path, &source_path).map_err(|e| {
+				io::Error::new(e.kind(), format!("Unable to create link \"{}\", {}", link.name, e))
+			})?;
+		}
+		return Ok(group_env);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::env;
+	use std::ffi::OsString;
+
+	use config::*;
+	use manager::Manager;
+	use test_dir::{TestDir, jdk_group, test_manager};
+
+	/// java groups jdk7 at 170 and jdk8 at 180 which dispatch and set JAVA_HOME, 180 is active
+	fn exec_manager(name: &str) -> (TestDir, Manager) {
+		let dir = TestDir::new(&format!("exec-{}", name));
+		let mut manager = test_manager(&dir);
+		for &(jdk, pri) in [("jdk7", 170), ("jdk8", 180)].iter() {
+			let mut group = jdk_group(&dir, jdk, pri, &["java"]);
+			group.kind = Some(LinkKind::Dispatch);
+			group.env.insert(String::from("JAVA_HOME"), String::from("${base}"));
+			manager.add_group(group).unwrap();
+		}
+		manager.config_mut().set_active(&String::from("java"), &180);
+		return (dir, manager);
+	}
+
+	#[test]
+	fn group_env_links_the_group_first_on_the_path() {
+		let (dir, manager) = exec_manager("path");
+		let group_env = manager.group_env(&String::from("java"), &GroupSelector::Closest(100)).unwrap();
+		assert_eq!(group_env.group().priority, 170);
+		let link_dir = group_env.link_dir().clone();
+		let (ref name, ref path) = group_env.vars()[0];
+		assert_eq!(name, "PATH");
+		assert_eq!(env::split_paths(path.as_ref().unwrap()).next().unwrap(), link_dir);
+		assert_eq!(group_env.vars()[1], (String::from("PMAN_JAVA"), Some(OsString::from("=170"))));
+		assert_eq!(group_env.vars()[2], (String::from("JAVA_HOME"), Some(dir.join("jdk7").into_os_string())));
+		// made as the platform default, the dispatcher cant find the config from there
+		let link_name = LinkKind::platform_default().backend().link_name("java", &dir.join("jdk7").join("java"));
+		assert!(link_dir.join(link_name).exists());
+	}
+
+	#[test]
+	fn group_env_changes_nothing_else() {
+		let (dir, manager) = exec_manager("scratch");
+		let group_env = manager.group_env(&String::from("java"), &GroupSelector::Exact(170)).unwrap();
+		let link_dir = group_env.link_dir().clone();
+		assert_eq!(manager.config().active_configs().get("java"), Some(&180));
+		assert!(!dir.join("cmd").exists());
+
+		drop(group_env);
+		assert!(!link_dir.exists());
+		assert!(manager.group_env(&String::from("java"), &GroupSelector::Exact(100)).is_err());
+	}
+}
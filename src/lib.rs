@@ -1,6 +1,30 @@
+//! pman manages groups of links to programs, eg. every installed jdk, and swaps which group is linked into a
+//! single command directory on the PATH. The pman binary is a command line front end over this library.
+//!
+//! `Manager` is the high level entry point, `Config` gives access to everything stored in the config file.
+
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate toml;
+
+pub mod config;
+pub mod error;
+mod manager;
+mod doctor;
+mod which;
+mod pins;
+mod dispatch;
+mod exec;
+mod discover;
 #[cfg(test)]
-mod tests {
-    #[test]
-    fn it_works() {
-    }
-}
+mod test_dir;
+
+pub use config::{Config, CfgLocation, CfgSource, LinkGroup, Link, LinkKind, LinkBackend, GroupSelector, GroupMode, Version, VersionReq, VersionOp, VersionFile, Pin};
+pub use error::PmanError;
+pub use manager::Manager;
+pub use doctor::{Finding, Fix, Severity};
+pub use which::{LinkOwner, Resolution};
+pub use pins::{Selection, SelectionSource, override_var, check_group_name};
+pub use exec::GroupEnv;
+pub use discover::Discovered;
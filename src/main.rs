@@ -1,56 +1,138 @@
+extern crate pman;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+
 use std::env;
-use std::ascii::AsciiExt;
 use commands::*;
-use config::*;
+use output::Format;
+use overrides::Overrides;
+// the commands reach the library through these, eg. config::Config
+use pman::{config, error};
+use pman::{CfgLocation, Config, Manager, PmanError};
+use pman::config::LOCK_TIMEOUT;
 
 mod commands;
-mod config;
+mod output;
+mod overrides;
 mod utils;
 
 fn main() {
 	let args: Vec<_> = env::args().collect();
 	
-	match Config::read() {
-		Ok(cfg_opt) => {
-			if args.len() < 2 {
-				println!("Not enough parameters supplied. Minimum of 1 parameters required");
-				help::print_help();
-				std::process::exit(1);
-			}
-			let (goal_args, addl_args) = args.split_at(2);
-			run_pman(&goal_args[1], cfg_opt, addl_args);
+	// the only place pman exits from, every error is mapped to its exit code here. See PmanError for the list
+	if let Err(e) = run(&args) {
+		// the command exec ran has its own output, dont mix anything into it
+		if let PmanError::Exited(_) = e {} else {
+			eprintln!("{}", e);
+		}
+		std::process::exit(e.exit_code());
+	}
+}
+
+fn run(args: &[String]) -> Result<(), PmanError> {
+	let (overrides, args) = Overrides::parse(args)?;
+	let location = overrides.locate_cfg()?;
+	// held from reading the config until pman exits, so parallel runs cant overwrite each others changes. Only commands which
+	// change something take it, the config is replaced atomically so reading it doesnt have to wait
+	let _lock;
+	if changes_config(&args) {
+		_lock = Some(Config::lock(&location.path, LOCK_TIMEOUT)?);
+		// migrating writes the config, so only with the lock held. Without it a legacy config is read as it is
+		match Config::migrate(&location.path) {
+			// on stderr, stdout may be json
+			Ok(Some(legacy_path)) => eprintln!("Migrated configuration to the new format, the old file was kept at \"{}\"", legacy_path.display()),
+			Ok(None) => {},
+			// reading it fails the same way, see below
+			Err(_) if is_restoring(&args) => {},
+			Err(e) => return Err(e)
+		}
+	} else {
+		_lock = None;
+	}
+	let mut cfg_opt = match Config::read_from(&location.path) {
+		Ok(cfg_opt) => cfg_opt,
+		Err(ref e) if is_restoring(&args) => {
+			// a broken config is what backups are for, let the restore go ahead without it
+			eprintln!("Error occurred while reading configuration, {}", e);
+			None
 		},
-		Err(e) => {
-			println!("Error occured while reading configuration, {}", e);
+		Err(e) => return Err(e)
+	};
+	if let Some(ref mut config) = cfg_opt {
+		overrides.apply(config);
+		// commands which only read make nothing, not even the command directory
+		if _lock.is_some() {
+			config.ensure_cmd_dir()?;
 		}
 	}
+	if args.len() < 2 {
+		help::print_help();
+		return Err(PmanError::InvalidArgs(String::from("Not enough parameters supplied. Minimum of 1 parameters required")));
+	}
+	let (goal_args, addl_args) = args.split_at(2);
+	return run_pman(&goal_args[1], cfg_opt, location, &overrides, addl_args);
 }
-fn run_pman(goal: &String, config_opt: Option<Config>, addl_args: &[String]) {
-	
+
+/// checks if args are for a command which may change the config or the command directory. Help, exec and init, list, which,
+/// current and env only read, so do doctor without -fix and configure with only -where
+fn changes_config(args: &[String]) -> bool {
+	if args.len() < 2 {
+		return false;
+	}
+	let addl_args = &args[2..];
+	match args[1].to_ascii_lowercase().as_ref() {
+		"help" | "exec" | "init" | "list" | "which" | "current" | "env" => return false,
+		"doctor" => return addl_args.iter().any(|a| a.eq_ignore_ascii_case("-fix") || a.eq_ignore_ascii_case("--fix")),
+		"configure" => return !addl_args.iter().all(|a| ["-w", "-where", "--where"].contains(&a.to_ascii_lowercase().as_ref())),
+		_ => return true
+	}
+}
+
+/// checks if args are for 'pman configure --restore-backup'
+fn is_restoring(args: &[String]) -> bool {
+	return args.len() > 1 && "configure".eq_ignore_ascii_case(&args[1])
+		&& args[2..].iter().any(|a| a.eq_ignore_ascii_case("--restore-backup") || a.eq_ignore_ascii_case("-restore-backup"));
+}
+
+fn run_pman(goal: &String, config_opt: Option<Config>, location: CfgLocation, overrides: &Overrides, addl_args: &[String]) -> Result<(), PmanError> {
+	let format = overrides.format;
+	if format == Format::Json && !["list", "which", "doctor", "current", "configure", "help"].contains(&goal.to_ascii_lowercase().as_ref()) {
+		return Err(PmanError::InvalidArgs(format!("{} has no json output, only list, which, doctor, current and configure --where do", goal)));
+	}
+
 	// these 2 goals dont need configuration to run, check for those
 	if "help".eq_ignore_ascii_case(goal) {
-		help::run(addl_args);
+		return help::run(addl_args);
 	} else if "configure".eq_ignore_ascii_case(goal) {
-		configure::run(config_opt, addl_args);
+		return configure::run(config_opt, &location, format, addl_args);
 	} else {
 		// these goals require configuration to exist. Make sure it does
-		if let Some(mut config) = config_opt {
-			match goal.to_ascii_lowercase().as_ref() {
-				"group" => group::run(&mut config, addl_args),
-				"list" => list::run(config, addl_args),
-				"swap" => swap::run(config, addl_args),
-				"link" => link::run(config, addl_args),
-				"rebuild" => rebuild::run(config),
-				_ => {
-					println!("Unknown argument \"{}\"", goal);
-					help::print_help();
-					std::process::exit(2);
-				}
+		let config = config_opt.ok_or(PmanError::NotConfigured)?;
+		let mut manager = Manager::new(config, location.path);
+		manager.set_force(overrides.force);
+		match goal.to_ascii_lowercase().as_ref() {
+			"group" => return group::run(manager, addl_args),
+			"list" => return list::run(manager, format, addl_args),
+			"swap" => return swap::run(manager, addl_args),
+			"link" => return link::run(manager, addl_args),
+			"rebuild" => return rebuild::run(manager),
+			"doctor" => return doctor::run(manager, format, addl_args),
+			"which" => return which::run(manager, format, addl_args),
+			"pin" => return pin::run(manager, addl_args),
+			"current" => return current::run(manager, format, addl_args),
+			"exec" => return exec::run(manager, addl_args),
+			"env" => return commands::env::run(manager, addl_args), // std::env is taken
+			"init" => return init::run(manager, addl_args),
+			"profile" => return profile::run(manager, addl_args),
+			"alias" => return alias::run(manager, addl_args),
+			"mode" => return mode::run(manager, addl_args),
+			"discover" => return discover::run(manager, addl_args),
+			_ => {
+				help::print_help();
+				return Err(PmanError::InvalidArgs(format!("Unknown argument \"{}\"", goal)));
 			}
-		} else {
-			println!("pman is not configured yet!\nRun 'pman help configure' for more information");
-			std::process::exit(1);
 		}
-		
 	}
 }
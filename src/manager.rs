@@ -0,0 +1,663 @@
+use std::io;
+use std::path::PathBuf;
+use std::slice;
+
+use config::*;
+use error::PmanError;
+use pins::check_group_name;
+
+/// High level operations over a config. Every change the manager makes is saved right away to the config file
+/// it was created with, changes made through config_mut are only saved with save. Managers which loaded their config
+/// hold its lock until they are dropped, so other pman runs wait for them.
+pub struct Manager {
+	config: Config,
+	cfg_path: PathBuf,
+	force: bool, // replace and remove files in the command directory pman doesnt own
+	_lock: Option<ConfigLock>
+}
+
+impl Manager {
+
+	/// manager for config which saves to cfg_path. The config file isnt locked, the caller should hold its lock
+	pub fn new(config: Config, cfg_path: PathBuf) -> Manager {
+		return Manager {
+			config: config,
+			cfg_path: cfg_path,
+			force: false,
+			_lock: None
+		};
+	}
+
+	/// locates the config file like pman does without --cfg, see Config::locate. PMAN_CONFIG, the per user locations and
+	/// finally .pman next to the executable are searched. Errors with NotConfigured when there is none
+	pub fn load() -> Result<Manager, PmanError> {
+		return Manager::load_from(Config::cfg_path()?);
+	}
+
+	/// locks and loads the given config file, a legacy config is migrated first. Errors with NotConfigured when there is none
+	/// and with Locked when another pman holds the config longer than LOCK_TIMEOUT
+	pub fn load_from(cfg_path: PathBuf) -> Result<Manager, PmanError> {
+		let lock = Config::lock(&cfg_path, LOCK_TIMEOUT)?;
+		Config::migrate(&cfg_path)?;
+		let config = Config::read_from(&cfg_path)?.ok_or(PmanError::NotConfigured)?;
+		config.ensure_cmd_dir()?;
+		let mut manager = Manager::new(config, cfg_path);
+		manager._lock = Some(lock);
+		return Ok(manager);
+	}
+
+	pub fn config(&self) -> &Config {
+		return &self.config;
+	}
+
+	pub fn config_mut(&mut self) -> &mut Config {
+		return &mut self.config;
+	}
+
+	/// the config file changes are saved to
+	pub fn cfg_path(&self) -> &PathBuf {
+		return &self.cfg_path;
+	}
+
+	/// lets the manager replace and remove files in the command directory which pman didnt make or which were changed since
+	pub fn set_force(&mut self, force: bool) {
+		self.force = force;
+	}
+
+	/// the command directory with its manifest, see Config::open_cmd_dir. Opening it leaves it as it was and its not pointed at
+	/// the config file, so its for reading it or removing links, see link_cmd_dir for making them
+	pub fn open_cmd_dir(&self) -> Result<CmdDir, PmanError> {
+		return Ok(self.config.open_cmd_dir(self.force)?);
+	}
+
+	/// the command directory to make links in. Dispatch links find the config through the command directory, so its
+	/// pointed at the config file of this manager first
+	pub fn link_cmd_dir(&self) -> Result<CmdDir, PmanError> {
+		let cmd_dir = self.open_cmd_dir()?;
+		cmd_dir.set_config_pointer(&self.cfg_path)?;
+		return Ok(cmd_dir);
+	}
+
+	/// writes the config to the config file
+	pub fn save(&self) -> Result<(), PmanError> {
+		return self.config.write_to(&self.cfg_path);
+	}
+
+	/// writes the env scripts of the command directory with the env vars of the active groups, see CmdDir::write_env_scripts
+	pub fn write_env_scripts(&self) -> Result<(), PmanError> {
+		self.open_cmd_dir()?.write_env_scripts(&self.config.active_env())?;
+		return Ok(());
+	}
+
+	/// adds a new group, a name in auto mode keeps its highest group active so it may be activated, see apply_mode.
+	/// Returns the group which was activated. Errors if the name already has a group at the same priority, one of its
+	/// links has the same name as a standalone link or the name cant be overridden, see check_group_name
+	pub fn add_group(&mut self, group: LinkGroup) -> Result<Option<LinkGroup>, PmanError> {
+		check_group_name(&group.name)?;
+		self.check_links(&group)?;
+		let name = group.name.to_owned();
+		self.config.insert_config_entry(group)?;
+		self.save()?;
+		return self.apply_mode(&name);
+	}
+
+	/// errors with LinkConflict if a link of group has the same name as a standalone link
+	pub(crate) fn check_links(&self, group: &LinkGroup) -> Result<(), PmanError> {
+		for link in &group.links {
+			if self.config.has_link(&link.name) {
+				return Err(PmanError::LinkConflict {
+					name: link.name.to_owned(),
+					reason: String::from("a standalone link has the same name")
+				});
+			}
+		}
+		return Ok(());
+	}
+
+	/// activates the group of the given name which the selector picks, returns the activated group
+	pub fn swap(&mut self, name: &String, selector: &GroupSelector) -> Result<LinkGroup, PmanError> {
+		let group = self.config.select(name, selector).ok_or_else(|| {
+			PmanError::MissingGroup {
+				name: name.to_owned(),
+				selector: selector.to_string()
+			}
+		})?;
+		self.activate(&group)?;
+		return Ok(group);
+	}
+
+	/// makes group the active group of its name in place of the group which was active before. The links are swapped as one
+	/// change, see LinkSwap, if anything fails the old links and config are left as they were. The group was picked by
+	/// hand so its name goes into manual mode
+	pub fn activate(&mut self, group: &LinkGroup) -> Result<(), PmanError> {
+		return self.activate_all(slice::from_ref(group));
+	}
+
+	/// makes every group the active group of its name as one change, like activate does for one group. If any of them
+	/// fails none of them is activated. The groups need different names
+	pub fn activate_all(&mut self, groups: &[LinkGroup]) -> Result<(), PmanError> {
+		return self._activate_all(groups, Some(GroupMode::Manual));
+	}
+
+	/// Keeps a name in auto mode on its highest group, its activated if another group or none is active. Names in manual
+	/// mode are left alone. Returns the group which was activated
+	pub fn apply_mode(&mut self, name: &String) -> Result<Option<LinkGroup>, PmanError> {
+		if self.config.mode(name) != GroupMode::Auto {
+			return Ok(None);
+		}
+		match self.config.select_highest_group(name) {
+			Some(ref highest) if !self.config.is_active(highest) => {
+				self._activate_all(slice::from_ref(highest), None)?;
+				return Ok(Some(highest.clone()));
+			},
+			_ => return Ok(None)
+		}
+	}
+
+	/// Puts name in mode, in auto mode the highest group is activated right away. Returns the group which was activated.
+	/// Errors with MissingGroup if there are no groups of the name
+	pub fn set_mode(&mut self, name: &String, mode: GroupMode) -> Result<Option<LinkGroup>, PmanError> {
+		let highest = self.config.select_highest_group(name).ok_or_else(|| {
+			PmanError::MissingGroup {
+				name: name.to_owned(),
+				selector: String::from("*")
+			}
+		})?;
+		if mode == GroupMode::Auto && !self.config.is_active(&highest) {
+			self._activate_all(slice::from_ref(&highest), Some(mode))?;
+			return Ok(Some(highest));
+		}
+		self.config.set_mode(name, mode);
+		self.save()?;
+		return Ok(None);
+	}
+
+	/// Removes the group of name with priority, its links are removed if it was active. When the active group of a name
+	/// in manual mode is removed the name goes back to auto mode, like update-alternatives does, so either way the
+	/// highest group left is activated in its place. Returns that group. A name without groups left keeps its mode
+	pub fn remove_group(&mut self, name: &String, priority: u64) -> Result<Option<LinkGroup>, PmanError> {
+		let group = self.config.select_group(name, priority).ok_or_else(|| {
+			PmanError::MissingGroup {
+				name: name.to_owned(),
+				selector: format!("={}", priority)
+			}
+		})?;
+		if !self.config.is_active(&group) {
+			self.config.remove_config_entry(name, priority);
+			self.save()?;
+			return self.apply_mode(name);
+		}
+
+		// swapped to a group without links, so the links are only removed if all of them can be, see LinkSwap
+		let mut cmd_dir = self.open_cmd_dir()?;
+		let mut swap = LinkSwap::stage(&cmd_dir, Some(&group), &LinkGroup::new(name.to_owned(), priority))?;
+		swap.commit(&mut cmd_dir)?;
+		self.config.clear_active(name);
+		self.config.remove_config_entry(name, priority);
+		if let Err(e) = self.save() {
+			self.config.insert_config_entry(group)?;
+			self.config.set_active(name, &priority);
+			return Err(swap.rollback_after(&mut cmd_dir, e));
+		}
+		// the links are gone either way, failing to clean up only leaves them behind in the work directory
+		let _ = swap.finish();
+
+		let new_group;
+		if self.config.mode(name) == GroupMode::Manual && self.config.select_highest_group(name).is_some() {
+			new_group = self.set_mode(name, GroupMode::Auto)?;
+		} else {
+			new_group = self.apply_mode(name)?;
+		}
+		// the env vars of the removed group go too, the group is gone by now so failing to write them is only worth a warning
+		if let Err(e) = self.write_env_scripts() {
+			eprintln!("Warning, the env scripts couldnt be updated, {}", e);
+		}
+		return Ok(new_group);
+	}
+
+	/// activates the groups like activate_all, with a mode the names of the groups are put in it
+	fn _activate_all(&mut self, groups: &[LinkGroup], mode: Option<GroupMode>) -> Result<(), PmanError> {
+		let mut cmd_dir = self.link_cmd_dir()?;
+		let mut swaps = Vec::new();
+		for group in groups.iter() {
+			match LinkSwap::stage(&cmd_dir, self.config.active_config_entry(&group.name), group) {
+				Ok(swap) => swaps.push(swap),
+				Err(e) => {
+					// nothing was committed, the staged links only have to be cleaned up
+					for swap in swaps {
+						let _ = swap.finish();
+					}
+					return Err(PmanError::from(e));
+				}
+			}
+		}
+		for i in 0..swaps.len() {
+			// a failed commit undoes itself, the ones before it have to be undone here
+			if let Err(e) = swaps[i].commit(&mut cmd_dir) {
+				let e = _undo(&mut swaps[..i], &mut cmd_dir, PmanError::from(e));
+				for swap in swaps {
+					let _ = swap.finish();
+				}
+				return Err(e);
+			}
+		}
+
+		// the groups only count as active once all their links are in place
+		let prev_active = self.config.active_configs().clone();
+		let prev_previous: Vec<Option<u64>> = groups.iter().map(|g| self.config.previous(&g.name)).collect();
+		let prev_modes: Vec<GroupMode> = groups.iter().map(|g| self.config.mode(&g.name)).collect();
+		for group in groups.iter() {
+			self.config.swap_active(&group.name, &group.priority);
+			if let Some(mode) = mode {
+				self.config.set_mode(&group.name, mode);
+			}
+		}
+		if let Err(e) = self.save() {
+			for ((group, previous), prev_mode) in groups.iter().zip(prev_previous).zip(prev_modes) {
+				match prev_active.get(&group.name) {
+					Some(priority) => self.config.set_active(&group.name, priority),
+					None => self.config.clear_active(&group.name)
+				}
+				self.config.set_previous(&group.name, previous);
+				self.config.set_mode(&group.name, prev_mode);
+			}
+			return Err(_undo(&mut swaps, &mut cmd_dir, e));
+		}
+		// the swaps are done either way, failing to clean up only leaves the old links behind in the work directories
+		for swap in swaps {
+			let _ = swap.finish();
+		}
+		// the groups are active by now, stale env scripts are worth a warning but dont undo that
+		if let Err(e) = self.write_env_scripts() {
+			eprintln!("Warning, the env scripts couldnt be updated, {}", e);
+		}
+		return Ok(());
+	}
+
+	/// adds a standalone link and creates it in the command directory, a standalone link with the same name is replaced.
+	/// Returns the path the link points to
+	pub fn add_link(&mut self, link: Link) -> Result<PathBuf, PmanError> {
+		if let Some(group) = self.config.group_with_link(&link.name) {
+			return Err(PmanError::LinkConflict {
+				name: link.name.to_owned(),
+				reason: format!("group {}/{} has a link with the same name", group.name, group.priority)
+			});
+		}
+
+		// the new link is staged before the old one is touched, if anything fails the old link stays, see LinkSwap
+		let mut cmd_dir = self.link_cmd_dir()?;
+		let dir = cmd_dir.path().clone();
+		let old_link = self.config.get_link(&link.name).cloned();
+		let old_group = old_link.as_ref().map(|old_link| _standalone_group(old_link, &dir));
+		let mut swap = LinkSwap::stage(&cmd_dir, old_group.as_ref(), &_standalone_group(&link, &dir))?;
+		swap.commit(&mut cmd_dir)?;
+
+		let (_, source_path, _) = link.rel_link(&dir, &dir, None);
+		self.config.remove_link(&link.name);
+		self.config.add_link(link.clone());
+		if let Err(e) = self.save() {
+			self.config.remove_link(&link.name);
+			if let Some(old_link) = old_link {
+				self.config.add_link(old_link);
+			}
+			return Err(swap.rollback_after(&mut cmd_dir, e));
+		}
+		// failing to clean up only leaves the old link behind in the work directory
+		let _ = swap.finish();
+		return Ok(source_path);
+	}
+
+	/// removes a standalone link from the config and the command directory, returns the path the link pointed to
+	pub fn remove_link(&mut self, name: &String) -> Result<PathBuf, PmanError> {
+		let link = self.config.get_link(name).cloned().ok_or_else(|| PmanError::MissingLink(name.to_owned()))?;
+		let mut cmd_dir = self.open_cmd_dir()?;
+		let dir = cmd_dir.path().clone();
+		let result = link.remove_link(&mut cmd_dir, &dir, None);
+
+		// the link goes from the config even if the file couldnt be removed
+		self.config.remove_link(name);
+		self.save()?;
+		return result.map_err(PmanError::from);
+	}
+
+	/// makes every standalone link and the links of every active group again. Keeps going when a link fails,
+	/// the first failure is returned once all were tried. Files pman doesnt own are left alone unless forced,
+	/// refusing to replace one fails with LinkConflict
+	pub fn rebuild(&self) -> Result<(), PmanError> {
+		let mut cmd_dir = self.link_cmd_dir()?;
+		let dir = cmd_dir.path().clone();
+		let mut result = Ok(());
+		for link in self.config.links() {
+			if let Err(e) = link.create_link(&mut cmd_dir, &dir, None) {
+				if result.is_ok() {
+					result = match PmanError::from(e) {
+						PmanError::Io(e) => Err(PmanError::Io(io::Error::new(e.kind(), format!("Failed to create standalone link {}, {}", link.name, e)))),
+						conflict => Err(conflict)
+					};
+				}
+			}
+		}
+		for key in self.config.active_configs().keys() {
+			if let Some(active_entry) = self.config.active_config_entry(key) {
+				if let Err(e) = active_entry.activate(&mut cmd_dir) {
+					if result.is_ok() {
+						result = Err(PmanError::from(e));
+					}
+				}
+			}
+		}
+		if let Err(e) = self.write_env_scripts() {
+			if result.is_ok() {
+				result = Err(e);
+			}
+		}
+		return result;
+	}
+
+	/// activates the groups the profile selects as one change, see activate_all. Returns the groups
+	pub fn use_profile(&mut self, profile: &String) -> Result<Vec<LinkGroup>, PmanError> {
+		let groups = self.config.profile_groups(profile)?;
+		self.activate_all(&groups)?;
+		return Ok(groups);
+	}
+
+	/// all groups ordered by name then highest priority first. When a name is given only the groups of that name are listed,
+	/// errors if there are none
+	pub fn list(&self, name: Option<&String>) -> Result<Vec<&LinkGroup>, PmanError> {
+		if let Some(name) = name {
+			let cfg_vec = self.config.config_entrys_by_name(name).ok_or_else(|| {
+				PmanError::MissingGroup {
+					name: name.to_owned(),
+					selector: String::from("*")
+				}
+			})?;
+			return Ok(cfg_vec.iter().collect());
+		}
+
+		let mut names: Vec<&String> = self.config.config_map().keys().collect();
+		names.sort();
+		let mut groups = Vec::new();
+		for name in names {
+			groups.extend(self.config.config_map()[name].iter());
+		}
+		return Ok(groups);
+	}
+}
+
+/// a group holding only the standalone link, so it can be swapped like the links of a group. Standalone links are relative
+/// to the command directory
+fn _standalone_group(link: &Link, cmd_dir: &PathBuf) -> LinkGroup {
+	let mut group = LinkGroup::new(link.name.to_owned(), 0);
+	group.set_base_path(cmd_dir.to_string_lossy().into_owned());
+	group.add_link(link.clone());
+	return group;
+}
+
+/// rolls back committed swaps after e, the last one first so the manifest ends up as it was before the first.
+/// Returns e, with the rollback error added if undoing failed too
+fn _undo(swaps: &mut [LinkSwap], cmd_dir: &mut CmdDir, mut e: PmanError) -> PmanError {
+	for swap in swaps.iter_mut().rev() {
+		e = swap.rollback_after(cmd_dir, e);
+	}
+	return e;
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs;
+	use std::path::Path;
+	use std::path::PathBuf;
+
+	use config::*;
+	use error::PmanError;
+	use manager::Manager;
+	use test_dir::{TestDir, jdk_group, jdk_manager};
+
+	/// manager with a java group at 170 and 180, each with a java binary in its own dir
+	fn sample_manager(dir: &Path) -> Manager {
+		return jdk_manager(dir, &[("jdk170", 170), ("jdk180", 180)], &["java"]);
+	}
+
+	/// where the link named name to source is made in the command directory
+	fn link_path(manager: &Manager, name: &str, source: &Path) -> PathBuf {
+		return manager.config().cmd_dir().join(LinkKind::platform_default().backend().link_name(name, source));
+	}
+
+	#[test]
+	fn swap_activates_and_saves() {
+		let dir = TestDir::new("manager-swap");
+		let mut manager = sample_manager(&dir);
+		let java = String::from("java");
+
+		let group = manager.swap(&java, &GroupSelector::Closest(175)).unwrap();
+		assert_eq!(group.priority, 180);
+		assert_eq!(manager.config().active_configs().get("java"), Some(&180));
+		assert!(link_path(&manager, "java", &dir.join("jdk180").join("java")).exists());
+
+		match manager.swap(&java, &GroupSelector::Exact(175)) {
+			Err(PmanError::MissingGroup { .. }) => {},
+			_ => panic!("expected a missing group error")
+		}
+
+		let loaded = Manager::load_from(dir.join(".pman")).unwrap();
+		assert_eq!(loaded.config().active_configs().get("java"), Some(&180));
+		assert_eq!(loaded.list(None).unwrap().len(), 2);
+	}
+
+	#[test]
+	fn failed_save_undoes_swap() {
+		let dir = TestDir::new("manager-save");
+		let mut manager = sample_manager(&dir);
+		let java = String::from("java");
+		manager.swap(&java, &GroupSelector::Exact(170)).unwrap();
+
+		// a directory where the config file should be makes saving fail
+		let mut broken = Manager::new(Config::read_from(&dir.join(".pman")).unwrap().unwrap(), dir.clone());
+		assert!(broken.swap(&java, &GroupSelector::Exact(180)).is_err());
+		assert_eq!(broken.config().active_configs().get("java"), Some(&170));
+		assert!(fs::read_to_string(link_path(&broken, "java", &dir.join("jdk170").join("java"))).unwrap().contains("jdk170"));
+	}
+
+	#[test]
+	fn links_conflict_with_groups() {
+		let dir = TestDir::new("manager-conflict");
+		let mut manager = sample_manager(&dir);
+
+		let java = Link::new(String::from("java"), dir.join("jdk170").join("java").to_str().unwrap().to_owned());
+		match manager.add_link(java) {
+			Err(PmanError::LinkConflict { .. }) => {},
+			_ => panic!("expected a link conflict")
+		}
+
+		let tool = Link::new(String::from("tool"), dir.join("jdk170").join("java").to_str().unwrap().to_owned());
+		manager.add_link(tool).unwrap();
+		let mut group = LinkGroup::new(String::from("tools"), 1);
+		group.add_link(Link::new(String::from("tool"), String::from("tool")));
+		match manager.add_group(group) {
+			Err(PmanError::LinkConflict { .. }) => {},
+			_ => panic!("expected a link conflict")
+		}
+	}
+
+	#[test]
+	fn standalone_links_are_made_and_removed() {
+		let dir = TestDir::new("manager-links");
+		let mut manager = sample_manager(&dir);
+		let source_path = dir.join("jdk170").join("java");
+		let tool = Link::new(String::from("tool"), source_path.to_str().unwrap().to_owned());
+
+		assert_eq!(manager.add_link(tool).unwrap(), source_path);
+		let link_path = link_path(&manager, "tool", &source_path);
+		assert!(link_path.exists());
+
+		manager.remove_link(&String::from("tool")).unwrap();
+		assert!(!link_path.exists());
+		assert!(Manager::load_from(dir.join(".pman")).unwrap().config().links().is_empty());
+	}
+
+	#[test]
+	fn failed_link_replacement_keeps_the_old_link() {
+		let dir = TestDir::new("manager-relink");
+		let mut manager = sample_manager(&dir);
+		let tool = |jdk: &str| Link::new(String::from("tool"), dir.join(jdk).join("java").to_str().unwrap().to_owned());
+		let link_path = link_path(&manager, "tool", &dir.join("jdk170").join("java"));
+
+		manager.add_link(tool("jdk170")).unwrap();
+		assert!(manager.add_link(tool("jdk160")).is_err());
+		assert!(fs::read_to_string(&link_path).unwrap().contains("jdk170"));
+		assert_eq!(manager.config().get_link(&String::from("tool")).unwrap().path, tool("jdk170").path);
+
+		manager.add_link(tool("jdk180")).unwrap();
+		assert!(fs::read_to_string(&link_path).unwrap().contains("jdk180"));
+		assert_eq!(Manager::load_from(dir.join(".pman")).unwrap().config().links()[0].path, tool("jdk180").path);
+	}
+
+	#[test]
+	fn rebuild_reports_refused_links() {
+		let dir = TestDir::new("manager-rebuild");
+		let mut manager = sample_manager(&dir);
+		manager.swap(&String::from("java"), &GroupSelector::Exact(180)).unwrap();
+		let link_path = link_path(&manager, "java", &dir.join("jdk180").join("java"));
+		fs::remove_file(&link_path).unwrap();
+		fs::write(&link_path, "users own script").unwrap();
+
+		match manager.rebuild() {
+			Err(PmanError::LinkConflict { ref name, .. }) => assert_eq!(name, "java"),
+			r => panic!("expected a link conflict, got {:?}", r)
+		}
+		assert_eq!(fs::read_to_string(&link_path).unwrap(), "users own script");
+		manager.set_force(true);
+		manager.rebuild().unwrap();
+		assert!(fs::read_to_string(&link_path).unwrap().contains("jdk180"));
+	}
+
+	#[test]
+	fn removing_a_manual_group_falls_back_to_the_highest() {
+		let dir = TestDir::new("manager-fallback");
+		let mut manager = sample_manager(&dir);
+		let java = String::from("java");
+		manager.swap(&java, &GroupSelector::Exact(170)).unwrap();
+		assert_eq!(manager.config().mode(&java), GroupMode::Manual);
+
+		// like update-alternatives the name goes back to auto mode, and the highest group is recorded as active
+		assert_eq!(manager.remove_group(&java, 170).unwrap().map(|g| g.priority), Some(180));
+		assert_eq!(manager.config().mode(&java), GroupMode::Auto);
+		let loaded = Manager::load_from(dir.join(".pman")).unwrap();
+		assert_eq!(loaded.config().active_configs().get("java"), Some(&180));
+		assert!(fs::read_to_string(link_path(&manager, "java", &dir.join("jdk180").join("java"))).unwrap().contains("jdk180"));
+	}
+
+	#[test]
+	fn auto_mode_activates_higher_groups() {
+		let dir = TestDir::new("manager-auto");
+		let mut manager = sample_manager(&dir);
+		let java = String::from("java");
+		assert_eq!(manager.set_mode(&java, GroupMode::Auto).unwrap().map(|g| g.priority), Some(180));
+
+		assert_eq!(manager.add_group(jdk_group(&dir, "jdk190", 190, &["java"])).unwrap().map(|g| g.priority), Some(190));
+		assert!(fs::read_to_string(link_path(&manager, "java", &dir.join("jdk190").join("java"))).unwrap().contains("jdk190"));
+		assert!(manager.set_mode(&String::from("node"), GroupMode::Auto).is_err());
+	}
+
+	#[test]
+	fn manual_mode_keeps_the_active_group() {
+		let dir = TestDir::new("manager-manual");
+		let mut manager = sample_manager(&dir);
+		let java = String::from("java");
+		manager.swap(&java, &GroupSelector::Exact(170)).unwrap();
+
+		// even when a higher one shows up or an inactive one goes
+		assert!(manager.add_group(jdk_group(&dir, "jdk190", 190, &["java"])).unwrap().is_none());
+		assert_eq!(manager.remove_group(&java, 180).unwrap(), None);
+		assert_eq!(manager.config().active_configs().get("java"), Some(&170));
+		assert_eq!(manager.set_mode(&java, GroupMode::Auto).unwrap().map(|g| g.priority), Some(190));
+	}
+
+	#[test]
+	fn removing_the_last_group_keeps_the_mode() {
+		let dir = TestDir::new("manager-last");
+		let mut manager = sample_manager(&dir);
+		let ant = String::from("ant");
+		let mut group = LinkGroup::new(ant.clone(), 1);
+		group.set_base_path(dir.join("jdk170").to_str().unwrap().to_owned());
+		group.env.insert(String::from("ANT_HOME"), String::from("${base}"));
+		manager.add_group(group).unwrap();
+		manager.swap(&ant, &GroupSelector::Exact(1)).unwrap();
+		let script_path = manager.config().cmd_dir().join(format!("{}.sh", ENV_SCRIPT));
+		assert!(fs::read_to_string(&script_path).unwrap().contains("ANT_HOME"));
+
+		assert_eq!(manager.remove_group(&ant, 1).unwrap(), None);
+		assert_eq!(manager.config().mode(&ant), GroupMode::Manual);
+		assert!(!fs::read_to_string(&script_path).unwrap().contains("ANT_HOME"));
+	}
+
+	#[test]
+	fn refused_removal_keeps_the_group() {
+		let dir = TestDir::new("manager-refused");
+		let mut manager = sample_manager(&dir);
+		let java = String::from("java");
+		manager.swap(&java, &GroupSelector::Exact(170)).unwrap();
+		let link_path = link_path(&manager, "java", &dir.join("jdk170").join("java"));
+		fs::write(&link_path, "edited").unwrap();
+
+		// the link was changed by hand, so its not removed and neither is the group
+		match manager.remove_group(&java, 170) {
+			Err(PmanError::LinkConflict { .. }) => {},
+			r => panic!("expected a link conflict, got {:?}", r)
+		}
+		assert_eq!(fs::read_to_string(&link_path).unwrap(), "edited");
+		assert_eq!(manager.config().active_configs().get("java"), Some(&170));
+		assert_eq!(manager.config().mode(&java), GroupMode::Manual);
+		let loaded = Manager::load_from(dir.join(".pman")).unwrap();
+		assert!(loaded.config().select_group(&java, 170).is_some());
+		assert_eq!(loaded.config().active_configs().get("java"), Some(&170));
+	}
+
+	#[test]
+	fn failed_removal_keeps_the_group() {
+		let dir = TestDir::new("manager-remove");
+		let mut manager = sample_manager(&dir);
+		let java = String::from("java");
+		manager.swap(&java, &GroupSelector::Exact(170)).unwrap();
+
+		// a failed save puts the links and the group back
+		let mut broken = Manager::new(Config::read_from(&dir.join(".pman")).unwrap().unwrap(), dir.to_path_buf());
+		assert!(broken.remove_group(&java, 170).is_err());
+		assert!(broken.config().select_group(&java, 170).is_some());
+		assert_eq!(broken.config().active_configs().get("java"), Some(&170));
+		assert!(fs::read_to_string(link_path(&manager, "java", &dir.join("jdk170").join("java"))).unwrap().contains("jdk170"));
+	}
+
+	#[test]
+	fn profiles_activate_all_or_nothing() {
+		let dir = TestDir::new("manager-profile");
+		let mut manager = sample_manager(&dir);
+		let (java, mvn, legacy) = (String::from("java"), String::from("mvn"), String::from("legacy"));
+		manager.swap(&java, &GroupSelector::Exact(180)).unwrap();
+		let mut maven = LinkGroup::new(mvn.clone(), 330);
+		maven.set_base_path(dir.join("maven").to_str().unwrap().to_owned());
+		maven.add_link(Link::new(mvn.clone(), String::from("mvn")));
+		manager.add_group(maven).unwrap();
+		manager.config_mut().set_profile_group(&legacy, &java, GroupSelector::Exact(170));
+		manager.config_mut().set_profile_group(&legacy, &mvn, GroupSelector::Highest);
+
+		// maven isnt installed, so java stays at 180 too
+		assert!(manager.use_profile(&legacy).is_err());
+		assert_eq!(manager.config().active_configs().get("java"), Some(&180));
+		assert_eq!(manager.config().active_configs().get("mvn"), None);
+		let link_path = link_path(&manager, "java", &dir.join("jdk180").join("java"));
+		assert!(fs::read_to_string(&link_path).unwrap().contains("jdk180"));
+
+		fs::create_dir_all(dir.join("maven")).unwrap();
+		fs::write(dir.join("maven").join("mvn"), "").unwrap();
+		assert_eq!(manager.use_profile(&legacy).unwrap().len(), 2);
+		assert!(manager.config().profile_matches(&legacy));
+		assert!(fs::read_to_string(&link_path).unwrap().contains("jdk170"));
+		let loaded = Manager::load_from(dir.join(".pman")).unwrap();
+		assert_eq!(loaded.config().active_configs().get("mvn"), Some(&330));
+		// only the links are left in the command directory, no work directories
+		assert_eq!(fs::read_dir(manager.config().cmd_dir()).unwrap().filter(|e| {
+			return e.as_ref().unwrap().file_name().to_string_lossy().starts_with(".pman-swap");
+		}).count(), 0);
+	}
+}
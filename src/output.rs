@@ -0,0 +1,49 @@
+use std::io;
+use std::io::prelude::*;
+
+use serde::Serialize;
+use serde_json;
+
+use error::PmanError;
+
+/// how commands print their results, picked with the global --format option
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+	/// for people, the default
+	Text,
+	/// for scripts, see the JSON output section of the readme for what each command prints
+	Json
+}
+
+impl Format {
+	pub fn parse(val: &str) -> Result<Format, PmanError> {
+		match val.to_ascii_lowercase().as_ref() {
+			"text" => return Ok(Format::Text),
+			"json" => return Ok(Format::Json),
+			_ => return Err(PmanError::InvalidArgs(format!("Unknown format \"{}\", expected text or json", val)))
+		}
+	}
+}
+
+/// The result of a read only command. Commands build one of these first and print it in the format asked for,
+/// so the text and json output never disagree
+pub trait Report: Serialize {
+	/// prints the text form
+	fn print_text(&self);
+}
+
+/// prints report in the given format, json is pretty printed so its readable too
+pub fn print<R: Report>(format: Format, report: &R) -> Result<(), PmanError> {
+	match format {
+		Format::Text => report.print_text(),
+		Format::Json => {
+			let json = serde_json::to_string_pretty(report).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+			// scripts often read only what they need and close the pipe, thats not an error
+			match writeln!(io::stdout(), "{}", json) {
+				Err(ref e) if e.kind() == io::ErrorKind::BrokenPipe => {},
+				result => result?
+			}
+		}
+	}
+	return Ok(());
+}
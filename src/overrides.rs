@@ -0,0 +1,146 @@
+use std::env;
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+use config::{CfgLocation, Config, CMD_DIR_ENV};
+use error::PmanError;
+use output::Format;
+
+/// config file and command directory used for one run of pman instead of the installed ones.
+/// They come from the global flags, the command directory from its env var when the flag isnt given. The config env var
+/// is part of the config search, see Config::locate. Nothing here is saved
+pub struct Overrides {
+	pub cfg_path: Option<PathBuf>,
+	pub cmd_dir: Option<PathBuf>,
+	pub force: bool, // --force, replace and remove files in the command directory pman doesnt own
+	pub format: Format
+}
+
+impl Overrides {
+
+	/// takes the global flags off the front of args (the binary name stays) and reads the env var of --cmd if it wasnt given.
+	/// Returns the overrides and the remaining args
+	pub fn parse(args: &[String]) -> Result<(Overrides, Vec<String>), PmanError> {
+		return Overrides::_parse(args, &|name| env::var_os(name));
+	}
+
+	fn _parse(args: &[String], env_var: &dyn Fn(&str) -> Option<OsString>) -> Result<(Overrides, Vec<String>), PmanError> {
+		let mut overrides = Overrides {
+			cfg_path: None,
+			cmd_dir: None,
+			force: false,
+			format: Format::Text
+		};
+
+		// global flags go before the command, eg. pman --cfg ci.pman swap java 180
+		let mut i = 1;
+		while i < args.len() {
+			if args[i] == "--force" {
+				overrides.force = true;
+				i += 1;
+				continue;
+			}
+			if args[i] == "--format" {
+				let val = args.get(i+1).ok_or_else(|| PmanError::InvalidArgs(String::from("Invalid syntax, option --format expects text or json after it")))?;
+				overrides.format = Format::parse(val)?;
+				i += 2;
+				continue;
+			}
+			let target = match args[i].as_ref() {
+				"--cfg" => &mut overrides.cfg_path,
+				"--cmd" => &mut overrides.cmd_dir,
+				_ => break
+			};
+			if i+1 >= args.len() || args[i+1] == "" {
+				return Err(PmanError::InvalidArgs(format!("Invalid syntax, option {} expects a path after it", args[i])));
+			}
+			*target = Some(Overrides::_absolute(PathBuf::from(&args[i+1]))?);
+			i += 2;
+		}
+
+		if overrides.cmd_dir.is_none() {
+			overrides.cmd_dir = Overrides::_from_env(env_var(CMD_DIR_ENV))?;
+		}
+
+		let mut rest = Vec::new();
+		rest.extend_from_slice(&args[..1.min(args.len())]);
+		rest.extend_from_slice(&args[i.min(args.len())..]);
+		return Ok((overrides, rest));
+	}
+
+	/// empty env vars count as not set
+	fn _from_env(val: Option<OsString>) -> Result<Option<PathBuf>, PmanError> {
+		match val {
+			Some(ref v) if !v.is_empty() => return Ok(Some(Overrides::_absolute(PathBuf::from(v))?)),
+			_ => return Ok(None)
+		}
+	}
+
+	/// relative paths are relative to the working directory, links need absolute paths
+	fn _absolute(path: PathBuf) -> Result<PathBuf, PmanError> {
+		if path.is_relative() {
+			return Ok(env::current_dir()?.join(path));
+		}
+		return Ok(path);
+	}
+
+	/// the config file to use for this run and why it was picked
+	pub fn locate_cfg(&self) -> Result<CfgLocation, PmanError> {
+		return Ok(Config::locate(self.cfg_path.clone())?);
+	}
+
+	/// points the config at the overridden command directory, if there is one
+	pub fn apply(&self, config: &mut Config) {
+		if self.cmd_dir.is_some() {
+			config.set_cmd_dir_override(self.cmd_dir.clone());
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::env;
+	use std::ffi::OsString;
+	use std::path::PathBuf;
+
+	use overrides::*;
+
+	fn args(vals: &[&str]) -> Vec<String> {
+		return vals.iter().map(|v| v.to_string()).collect();
+	}
+
+	#[test]
+	fn flags_are_taken_off_the_front() {
+		let no_env = |_: &str| -> Option<OsString> { None };
+		let (overrides, rest) = Overrides::_parse(&args(&["pman", "--cfg", "/ci/.pman", "--force", "--cmd", "bin", "swap", "java", "--cmd"]), &no_env).unwrap();
+		assert_eq!(overrides.cfg_path, Some(PathBuf::from("/ci/.pman")));
+		assert_eq!(overrides.cmd_dir, Some(env::current_dir().unwrap().join("bin")));
+		assert!(overrides.force);
+		assert_eq!(overrides.format, Format::Text);
+		assert_eq!(rest, args(&["pman", "swap", "java", "--cmd"])); // only flags before the command are global
+
+		assert!(Overrides::_parse(&args(&["pman", "--cfg"]), &no_env).is_err());
+		let (overrides, _) = Overrides::_parse(&args(&["pman", "--format", "json", "list"]), &no_env).unwrap();
+		assert_eq!(overrides.format, Format::Json);
+		assert!(Overrides::_parse(&args(&["pman", "--format", "xml", "list"]), &no_env).is_err());
+	}
+
+	#[test]
+	fn flags_win_over_env() {
+		let env = |name: &str| -> Option<OsString> {
+			match name {
+				CMD_DIR_ENV => return Some(OsString::from("/env/bin")),
+				_ => return None
+			}
+		};
+		let (overrides, _) = Overrides::_parse(&args(&["pman", "list"]), &env).unwrap();
+		assert_eq!(overrides.cmd_dir, Some(PathBuf::from("/env/bin")));
+
+		let (overrides, _) = Overrides::_parse(&args(&["pman", "--cmd", "/flag/bin", "list"]), &env).unwrap();
+		assert_eq!(overrides.cmd_dir, Some(PathBuf::from("/flag/bin")));
+
+		let empty = |_: &str| -> Option<OsString> { Some(OsString::from("")) };
+		let (overrides, _) = Overrides::_parse(&args(&["pman", "list"]), &empty).unwrap();
+		assert_eq!(overrides.cmd_dir, None);
+	}
+}
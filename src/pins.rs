@@ -0,0 +1,225 @@
+use std::env;
+use std::ffi::OsString;
+use std::path::Path;
+use std::path::PathBuf;
+
+use config::*;
+use error::PmanError;
+use manager::Manager;
+
+/// why a group is the one used for a name
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SelectionSource {
+	/// the PMAN_<NAME> env var overrides it for this run, see override_var
+	Env { var: String, selector: String },
+	/// a .pman-versions file pins it, selector is the selector as written in the file
+	Pinned { file: PathBuf, line: usize, selector: String },
+	/// its the active group, nothing pins the name
+	Active,
+	/// nothing pins the name and no group of it is active
+	None
+}
+
+/// the group used for a name in a directory, see Manager::current
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Selection {
+	pub name: String,
+	/// priority of the group, None when no group matches
+	pub priority: Option<u64>,
+	pub base_path: Option<String>,
+	pub source: SelectionSource
+}
+
+impl Manager {
+
+	/// the group used for every group name in dir. A name with its PMAN_<NAME> env var set uses the group the var selects,
+	/// a name pinned by a .pman-versions file in dir or above the group the nearest pin selects, otherwise the active group.
+	/// Pinned names without groups are included, their priority is None
+	pub fn current(&self, dir: &Path) -> Result<Vec<Selection>, PmanError> {
+		let files = VersionFile::find_all(dir)?;
+		let mut names: Vec<&String> = self.config().config_map().keys().collect();
+		for file in files.iter() {
+			for pin in file.pins() {
+				names.push(&pin.name);
+			}
+		}
+		names.sort();
+		names.dedup();
+
+		let mut selections = Vec::new();
+		for name in names {
+			selections.push(self._select_in(name, &files, &|var| env::var_os(var))?);
+		}
+		return Ok(selections);
+	}
+
+	/// the group used for name in dir, see current
+	pub fn current_of(&self, dir: &Path, name: &String) -> Result<Selection, PmanError> {
+		return self._select_in(name, &VersionFile::find_all(dir)?, &|var| env::var_os(var));
+	}
+
+	pub(crate) fn _select_in(&self, name: &String, files: &Vec<VersionFile>, env_var: &dyn Fn(&str) -> Option<OsString>) -> Result<Selection, PmanError> {
+		let config = self.config();
+		let var = override_var(name);
+		if let Some(val) = env_var(&var).filter(|v| !v.is_empty()) {
+			let val = val.to_string_lossy().into_owned();
+			let selector = GroupSelector::parse(&val).ok_or_else(|| {
+				PmanError::InvalidArgs(format!("{}={} isnt a priority or version, expected eg. 170, =170, ^1.8, highest or an alias", var, val))
+			})?;
+			let group = config.select(name, &selector);
+			return Ok(Selection {
+				name: name.to_owned(),
+				priority: group.as_ref().map(|g| g.priority),
+				base_path: group.map(|g| g.base_path),
+				source: SelectionSource::Env { var: var, selector: val }
+			});
+		}
+
+		for file in files.iter() {
+			if let Some(pin) = file.get(name) {
+				let group = config.select(name, &pin.selector);
+				return Ok(Selection {
+					name: name.to_owned(),
+					priority: group.as_ref().map(|g| g.priority),
+					base_path: group.map(|g| g.base_path),
+					source: SelectionSource::Pinned {
+						file: file.path().clone(),
+						line: pin.line,
+						selector: pin.selector.spec()
+					}
+				});
+			}
+		}
+
+		let active = config.active_config_entry(name);
+		return Ok(Selection {
+			name: name.to_owned(),
+			priority: active.map(|g| g.priority),
+			base_path: active.map(|g| g.base_path.to_owned()),
+			source: if active.is_some() { SelectionSource::Active } else { SelectionSource::None }
+		});
+	}
+
+	/// pins name to the group selector picks in the .pman-versions file in dir, the file is made if there is none.
+	/// Errors with MissingGroup if no group matches, returns the pinned group
+	pub fn pin(&self, dir: &Path, name: &String, selector: &GroupSelector) -> Result<LinkGroup, PmanError> {
+		let group = self.config().select(name, selector).ok_or_else(|| {
+			PmanError::MissingGroup {
+				name: name.to_owned(),
+				selector: selector.to_string()
+			}
+		})?;
+		let mut file = VersionFile::open(dir)?;
+		file.pin(name, selector.clone());
+		file.write()?;
+		return Ok(group);
+	}
+
+	/// removes the pin of name from the .pman-versions file in dir, returns false if it wasnt pinned there
+	pub fn unpin(&self, dir: &Path, name: &String) -> Result<bool, PmanError> {
+		let mut file = VersionFile::open(dir)?;
+		if !file.unpin(name) {
+			return Ok(false);
+		}
+		file.write()?;
+		return Ok(true);
+	}
+}
+
+/// env var which overrides the group used for name, PMAN_ and the name in upper case, eg. PMAN_JAVA.
+/// Characters which cant be in env var names become _
+pub fn override_var(name: &str) -> String {
+	let name: String = name.chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' }).collect();
+	return format!("PMAN_{}", name);
+}
+
+/// errors with InvalidArgs if the override var of name is one of the env vars pman itself reads, eg. a group named config
+/// would be overridden by PMAN_CONFIG
+pub fn check_group_name(name: &str) -> Result<(), PmanError> {
+	let var = override_var(name);
+	if [CONFIG_ENV, CMD_DIR_ENV, DISPATCHER_ENV].contains(&var.as_ref()) {
+		return Err(PmanError::InvalidArgs(format!("A group cant be named '{}', {} is taken by pman itself", name, var)));
+	}
+	return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+	use std::ffi::OsString;
+	use std::fs;
+
+	use config::*;
+	use error::PmanError;
+	use manager::Manager;
+	use pins::*;
+	use test_dir::{TestDir, jdk_manager};
+
+	/// java groups at 110, 170 and 180 with 180 active and a repo/src directory to pin in
+	fn java_manager(name: &str) -> (TestDir, Manager) {
+		let dir = TestDir::new(&format!("pins-{}", name));
+		fs::create_dir_all(dir.join("repo").join("src")).unwrap();
+		let mut manager = jdk_manager(&dir, &[("jdk110", 110), ("jdk170", 170), ("jdk180", 180)], &[]);
+		manager.config_mut().set_active(&String::from("java"), &180);
+		return (dir, manager);
+	}
+
+	#[test]
+	fn pins_win_over_the_active_group() {
+		let (dir, manager) = java_manager("pin");
+		let java = String::from("java");
+		let selection = manager.current_of(&dir.join("repo").join("src"), &java).unwrap();
+		assert_eq!((selection.priority, selection.source), (Some(180), SelectionSource::Active));
+
+		assert_eq!(manager.pin(&dir.join("repo"), &java, &GroupSelector::Closest(150)).unwrap().priority, 170);
+		assert!(manager.pin(&dir.join("repo"), &java, &GroupSelector::Exact(150)).is_err());
+		let selection = manager.current_of(&dir.join("repo").join("src"), &java).unwrap();
+		assert_eq!(selection.priority, Some(170));
+		assert_eq!(selection.source, SelectionSource::Pinned {
+			file: dir.join("repo").join(VERSIONS_FILE),
+			line: 1,
+			selector: String::from("150")
+		});
+
+		assert!(manager.unpin(&dir.join("repo"), &java).unwrap());
+		assert!(!dir.join("repo").join(VERSIONS_FILE).exists());
+		assert!(!manager.unpin(&dir.join("repo"), &java).unwrap());
+	}
+
+	#[test]
+	fn pins_of_names_without_groups_show_up() {
+		let (dir, manager) = java_manager("unknown");
+		fs::write(dir.join("repo").join("src").join(VERSIONS_FILE), "node 12\n").unwrap();
+		let current = manager.current(&dir.join("repo").join("src")).unwrap();
+		assert_eq!(current.len(), 2);
+		assert_eq!(current[1].name, "node");
+		assert_eq!(current[1].priority, None);
+	}
+
+	#[test]
+	fn the_env_var_wins_over_pins() {
+		let (dir, manager) = java_manager("env");
+		let java = String::from("java");
+		manager.pin(&dir.join("repo"), &java, &GroupSelector::Exact(170)).unwrap();
+
+		let env = |var: &str| if var == "PMAN_JAVA" { Some(OsString::from("=110")) } else { None };
+		let files = VersionFile::find_all(&dir.join("repo")).unwrap();
+		let selection = manager._select_in(&java, &files, &env).unwrap();
+		assert_eq!(selection.priority, Some(110));
+		assert_eq!(selection.source, SelectionSource::Env { var: String::from("PMAN_JAVA"), selector: String::from("=110") });
+		let bad = |_: &str| Some(OsString::from("1..8"));
+		assert!(manager._select_in(&java, &files, &bad).is_err());
+		assert_eq!(override_var("node-js"), "PMAN_NODE_JS");
+	}
+
+	#[test]
+	fn names_cant_take_pmans_own_vars() {
+		assert!(check_group_name("java").is_ok());
+		for name in ["config", "cmd-dir", "Dispatcher"].iter() {
+			match check_group_name(name) {
+				Err(PmanError::InvalidArgs(_)) => {},
+				_ => panic!("expected {} to be refused", name)
+			}
+		}
+	}
+}
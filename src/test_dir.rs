@@ -0,0 +1,73 @@
+use std::env;
+use std::fs;
+use std::ops::Deref;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+
+use config::*;
+use manager::Manager;
+
+/// A directory in the temp dir for one test, named after the test and this process so tests running in parallel dont
+/// share one. Its removed again when dropped, so its cleaned up when an assert fails too
+pub struct TestDir {
+	path: PathBuf
+}
+
+impl TestDir {
+
+	/// makes an empty pman-<name>-<pid> directory, whatever an earlier run left there is removed first
+	pub fn new(name: &str) -> TestDir {
+		let path = env::temp_dir().join(format!("pman-{}-{}", name, process::id()));
+		let _ = fs::remove_dir_all(&path);
+		fs::create_dir_all(&path).unwrap();
+		return TestDir { path: path };
+	}
+}
+
+impl Deref for TestDir {
+	type Target = PathBuf;
+
+	fn deref(&self) -> &PathBuf {
+		return &self.path;
+	}
+}
+
+impl AsRef<Path> for TestDir {
+	fn as_ref(&self) -> &Path {
+		return &self.path;
+	}
+}
+
+impl Drop for TestDir {
+	fn drop(&mut self) {
+		let _ = fs::remove_dir_all(&self.path);
+	}
+}
+
+/// manager saving to dir/.pman with its command directory at dir/cmd, neither is made here
+pub fn test_manager(dir: &Path) -> Manager {
+	return Manager::new(Config::new(dir.join("cmd").to_str().unwrap().to_owned()), dir.join(".pman"));
+}
+
+/// java group at pri with its base directory at dir/jdk, every one of bins is made there as an empty file and linked
+pub fn jdk_group(dir: &Path, jdk: &str, pri: u64, bins: &[&str]) -> LinkGroup {
+	let base = dir.join(jdk);
+	fs::create_dir_all(&base).unwrap();
+	let mut group = LinkGroup::new(String::from("java"), pri);
+	group.set_base_path(base.to_str().unwrap().to_owned());
+	for bin in bins.iter() {
+		fs::write(base.join(bin), "").unwrap();
+		group.add_link(Link::new(bin.to_string(), bin.to_string()));
+	}
+	return group;
+}
+
+/// test_manager with a jdk_group for each (jdk, pri) of jdks, linking bins. None of them is active
+pub fn jdk_manager(dir: &Path, jdks: &[(&str, u64)], bins: &[&str]) -> Manager {
+	let mut manager = test_manager(dir);
+	for &(jdk, pri) in jdks.iter() {
+		manager.add_group(jdk_group(dir, jdk, pri, bins)).unwrap();
+	}
+	return manager;
+}
@@ -1,12 +1,24 @@
 use std;
 use std::io::prelude::*;
 use std::slice::Iter;
+use config::{GroupSelector, LinkKind, Shell, VersionOp, VersionReq};
+use error::PmanError;
 
-pub fn loop_args<F, T>(iter: Iter<T>, mut func:F) where F:FnMut(&mut Iter<T>, &T) {
+/// calls func for each argument, func may pull more values off the iterator for arguments which take values.
+/// stops at the first error func returns
+pub fn loop_args<F, T>(iter: Iter<T>, mut func:F) -> Result<(), PmanError> where F:FnMut(&mut Iter<T>, &T) -> Result<(), PmanError> {
 	let mut m_iter = iter;
 	while let Some(v) = m_iter.next() {
-		func(&mut m_iter, v);
+		func(&mut m_iter, v)?;
 	}
+	return Ok(());
+}
+
+/// takes the value for the argument arg off of the iterator, expected describes the values arg takes for the error message
+pub fn next_arg<'a>(it: &mut Iter<'a, String>, arg: &String, expected: &str) -> Result<&'a String, PmanError> {
+	return it.next().ok_or_else(|| {
+		PmanError::InvalidArgs(format!("Invalid '{}' argument, expected {} but ran out of arguments", arg, expected))
+	});
 }
 
 /// prompts for user input and returns value into Option result
@@ -54,3 +66,46 @@ pub fn prompt_mut(question: &String, line: &mut String, default: &String) -> Opt
 	}
 	return Some(());
 }
+
+/// parses a link kind from a command line argument
+pub fn parse_link_kind(val: &String) -> Result<LinkKind, PmanError> {
+	return LinkKind::parse(val).ok_or_else(|| {
+		PmanError::InvalidArgs(format!("Unknown link kind '{}', expected one of: {}", val, link_kind_names()))
+	});
+}
+
+/// all link kind names joined for use in help and error text
+pub fn link_kind_names() -> String {
+	let names: Vec<&str> = LinkKind::all().iter().map(|k| k.as_str()).collect();
+	return names.join(", ");
+}
+
+/// Parses the <priority> argument of a command. Numbers are priorities where negative ones select the highest group,
+/// anything else is read like a selector in a .pman-versions file, eg. =170, highest, lts or a version like ^1.8.
+/// With exact a plain version like 1.8 has to match exactly instead of being a caret requirement
+pub fn parse_selector(val: &String, exact: bool) -> Result<GroupSelector, PmanError> {
+	if let Ok(priority) = val.parse::<i64>() {
+		return Ok(GroupSelector::from_priority(priority, exact));
+	}
+	match GroupSelector::parse(val) {
+		Some(GroupSelector::Version(ref req)) if exact && val.starts_with(|c: char| c.is_ascii_alphanumeric()) => {
+			return Ok(GroupSelector::Version(VersionReq::new(VersionOp::Exact, req.version().clone())));
+		},
+		Some(selector) => return Ok(selector),
+		None => return Err(PmanError::InvalidArgs(format!("Invalid priority '{}', expected a number, a version like ^1.8, \
+			highest, lowest, active, previous or an alias", val)))
+	}
+}
+
+/// parses a shell from a command line argument
+pub fn parse_shell(val: &String) -> Result<Shell, PmanError> {
+	return Shell::parse(val).ok_or_else(|| {
+		PmanError::InvalidArgs(format!("Unknown shell '{}', expected one of: {}", val, shell_names()))
+	});
+}
+
+/// all shell names joined for use in help and error text
+pub fn shell_names() -> String {
+	let names: Vec<&str> = Shell::all().iter().map(|s| s.as_str()).collect();
+	return names.join(", ");
+}
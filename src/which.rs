@@ -0,0 +1,202 @@
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use config::*;
+use doctor::same_dir;
+use error::PmanError;
+use manager::Manager;
+
+/// where a command in the command directory comes from
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum LinkOwner {
+	/// a standalone link
+	Standalone,
+	/// a link of the active group with this name and priority
+	Group { name: String, priority: u64 }
+}
+
+/// what a command in the command directory really runs, see Manager::which
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Resolution {
+	/// name of the link
+	pub name: String,
+	pub owner: LinkOwner,
+	pub kind: LinkKind,
+	/// the shim or link pman makes in the command directory
+	pub link_path: PathBuf,
+	pub link_exists: bool,
+	/// absolute path of the file the link runs
+	pub target: PathBuf,
+	pub target_exists: bool,
+	/// false when the command directory isnt on the PATH, the link cant be run by name then
+	pub on_path: bool,
+	/// executables of the same name which come before the command directory on the PATH and run instead of the link.
+	/// When the command directory isnt on the PATH this is every executable of the same name on it
+	pub shadowed_by: Vec<PathBuf>
+}
+
+impl Manager {
+
+	/// finds out what name runs when its typed on the command line. name is the name of a standalone link or a link of an
+	/// active group, the file name of the link in the command directory works too, eg. java.bat. Errors with MissingLink if
+	/// nothing links that name
+	pub fn which(&self, name: &String) -> Result<Resolution, PmanError> {
+		let config = self.config();
+		let dir = config.cmd_dir();
+
+		let mut found: Option<(Link, PathBuf, Option<LinkKind>, LinkOwner)> = None;
+		for link in config.links() {
+			if _matches(link, &dir, &dir, None, name) {
+				found = Some((link.clone(), dir.clone(), None, LinkOwner::Standalone));
+				break;
+			}
+		}
+		if found.is_none() {
+			let mut names: Vec<&String> = config.active_configs().keys().collect();
+			names.sort();
+			for group in names.into_iter().filter_map(|n| config.active_config_entry(n)) {
+				let source_dir = PathBuf::from(&group.base_path);
+				if let Some(link) = group.links.iter().find(|l| _matches(l, &dir, &source_dir, group.kind, name)) {
+					let owner = LinkOwner::Group { name: group.name.to_owned(), priority: group.priority };
+					found = Some((link.clone(), source_dir, group.kind, owner));
+					break;
+				}
+			}
+		}
+		let (link, source_dir, group_kind, owner) = found.ok_or_else(|| PmanError::MissingLink(name.to_owned()))?;
+
+		let (link_path, target, kind) = link.rel_link(&dir, &source_dir, group_kind);
+		let path_var = env::var_os("PATH");
+		return Ok(Resolution {
+			name: link.name.to_owned(),
+			owner: owner,
+			kind: kind,
+			link_exists: fs::symlink_metadata(&link_path).is_ok(),
+			link_path: link_path,
+			target_exists: target.exists(),
+			target: target,
+			on_path: path_var.as_ref().is_some_and(|v| env::split_paths(v).any(|d| same_dir(&d, &dir))),
+			shadowed_by: _shadowing(&dir, path_var, &_executables(&link.name))
+		});
+	}
+}
+
+/// checks if name is the name of link or the file name it has in the command directory
+fn _matches(link: &Link, cmd_dir: &PathBuf, source_dir: &PathBuf, group_kind: Option<LinkKind>, name: &String) -> bool {
+	if link.name.eq(name) {
+		return true;
+	}
+	let (link_path, _, _) = link.rel_link(cmd_dir, source_dir, group_kind);
+	return link_path.file_name().is_some_and(|n| n.to_string_lossy().eq_ignore_ascii_case(name));
+}
+
+/// every executable in the directories of path_var which one of file_names would run, up to cmd_dir.
+/// If cmd_dir isnt one of the directories every executable of those names is returned
+fn _shadowing(cmd_dir: &Path, path_var: Option<OsString>, file_names: &Vec<String>) -> Vec<PathBuf> {
+	let mut shadows = Vec::new();
+	let path_var = match path_var {
+		Some(v) => v,
+		None => return shadows
+	};
+	for dir in env::split_paths(&path_var) {
+		if same_dir(&dir, cmd_dir) {
+			break;
+		}
+		for file_name in file_names.iter() {
+			let path = dir.join(file_name);
+			if _is_executable(&path) && !shadows.contains(&path) {
+				shadows.push(path);
+			}
+		}
+	}
+	return shadows;
+}
+
+/// file names the shell tries for a command name, windows tries every extension in PATHEXT
+#[cfg(windows)]
+fn _executables(name: &str) -> Vec<String> {
+	let pathext = env::var("PATHEXT").unwrap_or_else(|_| String::from(".COM;.EXE;.BAT;.CMD"));
+	let mut names = Vec::new();
+	if Path::new(name).extension().is_some() {
+		names.push(name.to_owned());
+	}
+	for ext in pathext.split(';').filter(|e| !e.is_empty()) {
+		names.push(format!("{}{}", name, ext.to_ascii_lowercase()));
+	}
+	return names;
+}
+
+#[cfg(not(windows))]
+fn _executables(name: &str) -> Vec<String> {
+	return vec![name.to_owned()];
+}
+
+#[cfg(unix)]
+fn _is_executable(path: &Path) -> bool {
+	use std::os::unix::fs::PermissionsExt;
+	return fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0);
+}
+
+#[cfg(not(unix))]
+fn _is_executable(path: &Path) -> bool {
+	return fs::metadata(path).is_ok_and(|m| m.is_file());
+}
+
+#[cfg(test)]
+mod tests {
+	use std::env;
+	use std::fs;
+
+	use which::*;
+	use test_dir::{TestDir, jdk_manager};
+
+	fn scratch_dir(name: &str) -> TestDir {
+		let dir = TestDir::new(&format!("which-{}", name));
+		fs::create_dir_all(dir.join("cmd")).unwrap();
+		return dir;
+	}
+
+	#[test]
+	fn resolves_links_of_active_groups() {
+		let dir = scratch_dir("resolve");
+		let mut manager = jdk_manager(&dir, &[("jdk", 180)], &["java", "javac"]);
+
+		// nothing is linked until the group is active
+		assert!(manager.which(&String::from("java")).is_err());
+		manager.swap(&String::from("java"), &GroupSelector::Highest).unwrap();
+
+		let java = manager.which(&String::from("java")).unwrap();
+		assert_eq!(java.owner, LinkOwner::Group { name: String::from("java"), priority: 180 });
+		assert_eq!(java.target, dir.join("jdk").join("java"));
+		assert!(java.target_exists);
+		assert!(java.link_exists);
+		fs::remove_file(dir.join("jdk").join("javac")).unwrap();
+		assert!(!manager.which(&String::from("javac")).unwrap().target_exists);
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn finds_shadowing_executables() {
+		use std::os::unix::fs::PermissionsExt;
+
+		let dir = scratch_dir("shadow");
+		for bin in ["usr", "opt", "late"].iter() {
+			fs::create_dir_all(dir.join(bin)).unwrap();
+			fs::write(dir.join(bin).join("java"), "").unwrap();
+			fs::set_permissions(dir.join(bin).join("java"), fs::Permissions::from_mode(0o755)).unwrap();
+		}
+		// not executable, the shell skips it
+		fs::set_permissions(dir.join("opt").join("java"), fs::Permissions::from_mode(0o644)).unwrap();
+
+		let path_var = env::join_paths(vec![dir.join("usr"), dir.join("opt"), dir.join("cmd"), dir.join("late")]).unwrap();
+		let names = vec![String::from("java")];
+		assert_eq!(_shadowing(&dir.join("cmd"), Some(path_var), &names), vec![dir.join("usr").join("java")]);
+
+		let no_cmd = env::join_paths(vec![dir.join("usr"), dir.join("late")]).unwrap();
+		assert_eq!(_shadowing(&dir.join("cmd"), Some(no_cmd), &names).len(), 2);
+	}
+}